@@ -0,0 +1,119 @@
+// 后端持久化的应用设置，保存在 data_local_base()/settings.json
+use crate::{data_local_base, write_log};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+static SETTINGS: OnceCell<Mutex<AppSettings>> = OnceCell::new();
+
+// 管理器退出时如何处理仍在运行的浏览器
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitBehavior {
+    #[default]
+    CloseAll,
+    Detach,
+    Ask,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct AppSettings {
+    pub exit_behavior: ExitBehavior,
+    // 退出时并行关闭所有浏览器的总超时
+    pub shutdown_timeout_ms: u64,
+    // 同时运行的浏览器上限，0 表示不限
+    pub max_running_browsers: u32,
+    // 同时处于启动过程中的浏览器上限，0 表示不限
+    pub max_concurrent_launches: u32,
+    // 每个 profile 保留的快照数量，0 表示不限
+    pub max_snapshots_per_profile: u32,
+    // 离线 GeoIP(mmdb) 路径，为空时使用 data 目录下 geoip/GeoLite2-City.mmdb
+    pub geoip_db_path: Option<String>,
+    // 经代理访问以获取出口 IP 的回显服务
+    pub ip_echo_url: String,
+    // 启动时按代理出口 IP 对齐时区、语言与地理位置
+    pub geo_align: bool,
+    // 启动前检查代理，不可用时拒绝启动
+    pub proxy_check_before_launch: bool,
+    // 经本地中转连接代理，用于代理认证与流量统计
+    pub proxy_relay: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            exit_behavior: ExitBehavior::CloseAll,
+            shutdown_timeout_ms: 5000,
+            max_running_browsers: 0,
+            max_concurrent_launches: 3,
+            max_snapshots_per_profile: 5,
+            geoip_db_path: None,
+            ip_echo_url: "https://api.ipify.org".to_string(),
+            geo_align: true,
+            proxy_check_before_launch: false,
+            proxy_relay: true,
+        }
+    }
+}
+
+fn settings_path() -> PathBuf {
+    data_local_base().join("settings.json")
+}
+
+fn load_from_disk() -> AppSettings {
+    match fs::read_to_string(settings_path()) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            write_log("WARN", &format!("parse settings.json failed: {}", e));
+            AppSettings::default()
+        }),
+        Err(_) => AppSettings::default(),
+    }
+}
+
+fn settings() -> &'static Mutex<AppSettings> {
+    SETTINGS.get_or_init(|| Mutex::new(load_from_disk()))
+}
+
+pub(crate) fn current() -> AppSettings {
+    settings().lock().map(|s| s.clone()).unwrap_or_default()
+}
+
+fn save(next: &AppSettings) -> Result<(), String> {
+    let path = settings_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("create settings dir failed: {e}"))?;
+    }
+    let content = serde_json::to_string_pretty(next)
+        .map_err(|e| format!("serialize settings failed: {e}"))?;
+    fs::write(&path, content).map_err(|e| format!("write settings failed: {e}"))
+}
+
+#[tauri::command]
+pub(crate) fn app_settings_get() -> AppSettings {
+    current()
+}
+
+#[tauri::command]
+pub(crate) fn app_settings_update(settings: AppSettings) -> Result<AppSettings, String> {
+    save(&settings)?;
+    if let Ok(mut s) = self::settings().lock() {
+        *s = settings.clone();
+    }
+    write_log("INFO", &format!("app settings updated: {:?}", settings));
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_settings_json_falls_back_to_defaults() {
+        let s: AppSettings = serde_json::from_str(r#"{"exit_behavior":"ask"}"#).unwrap();
+        assert_eq!(s.exit_behavior, ExitBehavior::Ask);
+        assert_eq!(s.shutdown_timeout_ms, 5000);
+    }
+}
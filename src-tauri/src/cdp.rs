@@ -0,0 +1,173 @@
+// 最小化的同步 CDP 客户端：连接 DevToolsActivePort 给出的 browser 端点
+use crate::sessions;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+use tungstenite::{Message, WebSocket};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Target.attachedToTarget 事件
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Attached {
+    pub session_id: String,
+    pub target_type: String,
+    // 自动附加的新目标暂停在首个请求之前，需 resume 后才继续
+    pub waiting: bool,
+}
+
+impl Attached {
+    pub(crate) fn from_event(ev: &Value) -> Option<Self> {
+        if ev["method"] != "Target.attachedToTarget" {
+            return None;
+        }
+        let p = &ev["params"];
+        Some(Self {
+            session_id: p["sessionId"].as_str()?.to_string(),
+            target_type: p["targetInfo"]["type"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            waiting: p["waitingForDebugger"].as_bool().unwrap_or(false),
+        })
+    }
+}
+
+pub(crate) struct CdpClient {
+    socket: WebSocket<TcpStream>,
+    next_id: u64,
+    // 等待命令结果期间收到的事件
+    events: VecDeque<Value>,
+}
+
+impl CdpClient {
+    pub(crate) fn connect(ws_url: &str) -> Result<Self, String> {
+        let url: url::Url = ws_url
+            .parse()
+            .map_err(|e| format!("invalid devtools url: {e}"))?;
+        let host = url.host_str().unwrap_or("127.0.0.1");
+        let port = url.port().unwrap_or(80);
+        let stream = TcpStream::connect((host, port))
+            .map_err(|e| format!("connect devtools failed: {e}"))?;
+        stream
+            .set_read_timeout(Some(Duration::from_millis(200)))
+            .map_err(|e| format!("set devtools timeout failed: {e}"))?;
+        let (socket, _) = tungstenite::client(ws_url, stream)
+            .map_err(|e| format!("devtools handshake failed: {e}"))?;
+        Ok(Self {
+            socket,
+            next_id: 1,
+            events: VecDeque::new(),
+        })
+    }
+
+    // 连接正在运行的 profile；未开启远程调试时报错
+    pub(crate) fn for_label(label: &str) -> Result<Self, String> {
+        let session =
+            sessions::session(label).ok_or_else(|| format!("profile {} is not running", label))?;
+        let ws = session
+            .devtools_ws
+            .ok_or_else(|| format!("profile {} was started without remote debugging", label))?;
+        Self::connect(&ws)
+    }
+
+    fn read_message(&mut self) -> Result<Option<Value>, String> {
+        match self.socket.read() {
+            Ok(Message::Text(text)) => serde_json::from_str(&text)
+                .map(Some)
+                .map_err(|e| format!("parse devtools message failed: {e}")),
+            Ok(_) => Ok(None),
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(format!("devtools read failed: {e}")),
+        }
+    }
+
+    pub(crate) fn call(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        self.call_in(None, method, params)
+    }
+
+    // session_id 为 Target.attachToTarget(flatten) 返回的会话
+    pub(crate) fn call_in(
+        &mut self,
+        session_id: Option<&str>,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut msg = json!({ "id": id, "method": method, "params": params });
+        if let Some(sid) = session_id {
+            msg["sessionId"] = json!(sid);
+        }
+        self.socket
+            .send(Message::Text(msg.to_string()))
+            .map_err(|e| format!("devtools send failed: {e}"))?;
+        let deadline = Instant::now() + DEFAULT_TIMEOUT;
+        while Instant::now() < deadline {
+            let Some(v) = self.read_message()? else {
+                continue;
+            };
+            if v.get("method").is_some() {
+                self.events.push_back(v);
+                continue;
+            }
+            if v.get("id").and_then(|x| x.as_u64()) == Some(id) {
+                if let Some(err) = v.get("error") {
+                    return Err(format!(
+                        "{} failed: {}",
+                        method,
+                        err.get("message").and_then(|m| m.as_str()).unwrap_or("")
+                    ));
+                }
+                return Ok(v.get("result").cloned().unwrap_or(Value::Null));
+            }
+        }
+        Err(format!("{} timed out", method))
+    }
+
+    // 自动附加到现有与之后新建的目标；在页面会话上调用时覆盖其 iframe 与 worker
+    pub(crate) fn auto_attach(&mut self, session_id: Option<&str>) -> Result<(), String> {
+        self.call_in(
+            session_id,
+            "Target.setAutoAttach",
+            json!({ "autoAttach": true, "waitForDebuggerOnStart": true, "flatten": true }),
+        )
+        .map(|_| ())
+    }
+
+    pub(crate) fn resume(&mut self, attached: &Attached) -> Result<(), String> {
+        if !attached.waiting {
+            return Ok(());
+        }
+        self.call_in(
+            Some(&attached.session_id),
+            "Runtime.runIfWaitingForDebugger",
+            json!({}),
+        )
+        .map(|_| ())
+    }
+
+    // 读取下一条事件，超时返回 None
+    pub(crate) fn next_event(&mut self, timeout: Duration) -> Result<Option<Value>, String> {
+        if let Some(ev) = self.events.pop_front() {
+            return Ok(Some(ev));
+        }
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if let Some(v) = self.read_message()? {
+                if v.get("method").is_some() {
+                    return Ok(Some(v));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,759 @@
+// Cookie 导入导出：Netscape cookies.txt 与常见 JSON 数组格式
+// profile 关闭时直接读写 Chromium 的 Cookies 数据库，运行中则通过 CDP（写入用页面会话的 Network 域）
+use crate::cdp::CdpClient;
+use crate::{browser_running, vault, write_log};
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
+use rusqlite::{params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Chromium 时间戳为 1601-01-01 起的微秒数
+const WINDOWS_EPOCH_OFFSET_SECS: i64 = 11_644_473_600;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CookieFormat {
+    Netscape,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Unspecified,
+    NoRestriction,
+    Lax,
+    Strict,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    // 以 '.' 开头表示对子域名生效，否则为 host-only
+    pub domain: String,
+    pub path: String,
+    // unix 秒；None 表示会话 cookie
+    pub expires: Option<i64>,
+    pub secure: bool,
+    pub http_only: bool,
+    pub same_site: SameSite,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CookieImportSummary {
+    pub imported: usize,
+    pub skipped: usize,
+    // "database" 或 "cdp"
+    pub via: String,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CookieExport {
+    pub content: String,
+    pub exported: usize,
+    // 无法解密而未导出的条数
+    pub skipped: usize,
+    pub warnings: Vec<String>,
+}
+
+pub(crate) fn parse_netscape(content: &str) -> (Vec<Cookie>, Vec<String>) {
+    let mut cookies = Vec::new();
+    let mut warnings = Vec::new();
+    for (idx, raw) in content.lines().enumerate() {
+        let mut line = raw.trim_end_matches('\r');
+        let mut http_only = false;
+        if let Some(rest) = line.strip_prefix("#HttpOnly_") {
+            line = rest;
+            http_only = true;
+        } else if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 7 {
+            warnings.push(format!("line {}: expected 7 tab-separated fields", idx + 1));
+            continue;
+        }
+        let include_subdomains = fields[1].eq_ignore_ascii_case("TRUE");
+        let mut domain = fields[0].trim().to_string();
+        if include_subdomains && !domain.starts_with('.') {
+            domain.insert(0, '.');
+        }
+        let expires = match fields[4].trim().parse::<i64>() {
+            Ok(0) => None,
+            Ok(v) => Some(v),
+            Err(_) => {
+                warnings.push(format!("line {}: invalid expiry {:?}", idx + 1, fields[4]));
+                continue;
+            }
+        };
+        cookies.push(Cookie {
+            name: fields[5].to_string(),
+            // 值中可能包含制表符，剩余字段原样拼回
+            value: fields[6..].join("\t"),
+            domain,
+            path: fields[2].to_string(),
+            expires,
+            secure: fields[3].eq_ignore_ascii_case("TRUE"),
+            http_only,
+            same_site: SameSite::Unspecified,
+        });
+    }
+    (cookies, warnings)
+}
+
+pub(crate) fn to_netscape(cookies: &[Cookie]) -> String {
+    let mut out = String::from("# Netscape HTTP Cookie File\n");
+    for c in cookies {
+        let prefix = if c.http_only { "#HttpOnly_" } else { "" };
+        let tf = |b: bool| if b { "TRUE" } else { "FALSE" };
+        out.push_str(&format!(
+            "{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            prefix,
+            c.domain,
+            tf(c.domain.starts_with('.')),
+            c.path,
+            tf(c.secure),
+            c.expires.unwrap_or(0),
+            c.name,
+            c.value
+        ));
+    }
+    out
+}
+
+fn parse_same_site(v: Option<&Value>) -> SameSite {
+    match v
+        .and_then(|s| s.as_str())
+        .map(|s| s.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("no_restriction") | Some("none") => SameSite::NoRestriction,
+        Some("lax") => SameSite::Lax,
+        Some("strict") => SameSite::Strict,
+        _ => SameSite::Unspecified,
+    }
+}
+
+// 兼容 EditThisCookie/Cookie-Editor（expirationDate、hostOnly）与 CDP/Puppeteer（expires）
+pub(crate) fn parse_json(content: &str) -> Result<(Vec<Cookie>, Vec<String>), String> {
+    let v: Value =
+        serde_json::from_str(content).map_err(|e| format!("parse cookie json failed: {e}"))?;
+    let items = match &v {
+        Value::Array(a) => a.clone(),
+        Value::Object(o) => o
+            .get("cookies")
+            .and_then(|c| c.as_array())
+            .cloned()
+            .ok_or("cookie json must be an array")?,
+        _ => return Err("cookie json must be an array".into()),
+    };
+    let mut cookies = Vec::new();
+    let mut warnings = Vec::new();
+    for (idx, item) in items.iter().enumerate() {
+        let s = |k: &str| item.get(k).and_then(|v| v.as_str()).map(|s| s.to_string());
+        let b = |k: &str| item.get(k).and_then(|v| v.as_bool()).unwrap_or(false);
+        let (Some(name), Some(mut domain)) = (s("name"), s("domain")) else {
+            warnings.push(format!("item {}: missing name or domain", idx));
+            continue;
+        };
+        let host_only = item
+            .get("hostOnly")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(!domain.starts_with('.'));
+        if host_only {
+            domain = domain.trim_start_matches('.').to_string();
+        } else if !domain.starts_with('.') {
+            domain.insert(0, '.');
+        }
+        let expires = ["expirationDate", "expires"]
+            .iter()
+            .find_map(|k| item.get(*k).and_then(|v| v.as_f64()))
+            .filter(|e| *e > 0.0 && !b("session"))
+            .map(|e| e as i64);
+        cookies.push(Cookie {
+            name,
+            value: s("value").unwrap_or_default(),
+            domain,
+            path: s("path").unwrap_or_else(|| "/".into()),
+            expires,
+            secure: b("secure"),
+            http_only: b("httpOnly"),
+            same_site: parse_same_site(item.get("sameSite")),
+        });
+    }
+    Ok((cookies, warnings))
+}
+
+pub(crate) fn to_json(cookies: &[Cookie]) -> String {
+    let items: Vec<Value> = cookies
+        .iter()
+        .map(|c| {
+            let same_site = match c.same_site {
+                SameSite::Unspecified => "unspecified",
+                SameSite::NoRestriction => "no_restriction",
+                SameSite::Lax => "lax",
+                SameSite::Strict => "strict",
+            };
+            let mut v = json!({
+                "domain": c.domain,
+                "hostOnly": !c.domain.starts_with('.'),
+                "name": c.name,
+                "value": c.value,
+                "path": c.path,
+                "secure": c.secure,
+                "httpOnly": c.http_only,
+                "sameSite": same_site,
+                "session": c.expires.is_none(),
+            });
+            if let Some(e) = c.expires {
+                v["expirationDate"] = json!(e);
+            }
+            v
+        })
+        .collect();
+    serde_json::to_string_pretty(&items).unwrap_or_else(|_| "[]".into())
+}
+
+fn parse_cookies(
+    content: &str,
+    format: CookieFormat,
+) -> Result<(Vec<Cookie>, Vec<String>), String> {
+    match format {
+        CookieFormat::Netscape => Ok(parse_netscape(content)),
+        CookieFormat::Json => parse_json(content),
+    }
+}
+
+fn format_cookies(cookies: &[Cookie], format: CookieFormat) -> String {
+    match format {
+        CookieFormat::Netscape => to_netscape(cookies),
+        CookieFormat::Json => to_json(cookies),
+    }
+}
+
+fn same_site_to_db(s: SameSite) -> i64 {
+    match s {
+        SameSite::Unspecified => -1,
+        SameSite::NoRestriction => 0,
+        SameSite::Lax => 1,
+        SameSite::Strict => 2,
+    }
+}
+
+fn same_site_from_db(v: i64) -> SameSite {
+    match v {
+        0 => SameSite::NoRestriction,
+        1 => SameSite::Lax,
+        2 => SameSite::Strict,
+        _ => SameSite::Unspecified,
+    }
+}
+
+fn to_chrome_time(unix_secs: i64) -> i64 {
+    (unix_secs + WINDOWS_EPOCH_OFFSET_SECS) * 1_000_000
+}
+
+fn from_chrome_time(t: i64) -> i64 {
+    t / 1_000_000 - WINDOWS_EPOCH_OFFSET_SECS
+}
+
+// 新版 Chromium 放在 Default/Network/Cookies，旧版在 Default/Cookies
+fn cookies_db_path(label: &str) -> PathBuf {
+    let default_dir = vault::user_data_dir(label).join("Default");
+    let legacy = default_dir.join("Cookies");
+    if legacy.exists() {
+        return legacy;
+    }
+    default_dir.join("Network").join("Cookies")
+}
+
+// 数据库不存在（profile 从未启动过）时按 v21 结构新建，Chromium 启动后会自行迁移
+const COOKIES_SCHEMA_V21: &str = "
+CREATE TABLE meta(key LONGVARCHAR NOT NULL UNIQUE PRIMARY KEY, value LONGVARCHAR);
+INSERT INTO meta(key, value) VALUES('version', '21'), ('last_compatible_version', '21');
+CREATE TABLE cookies(creation_utc INTEGER NOT NULL, host_key TEXT NOT NULL,
+  top_frame_site_key TEXT NOT NULL, name TEXT NOT NULL, value TEXT NOT NULL,
+  encrypted_value BLOB NOT NULL, path TEXT NOT NULL, expires_utc INTEGER NOT NULL,
+  is_secure INTEGER NOT NULL, is_httponly INTEGER NOT NULL, last_access_utc INTEGER NOT NULL,
+  has_expires INTEGER NOT NULL, is_persistent INTEGER NOT NULL, priority INTEGER NOT NULL,
+  samesite INTEGER NOT NULL, source_scheme INTEGER NOT NULL, source_port INTEGER NOT NULL,
+  last_update_utc INTEGER NOT NULL,
+  UNIQUE (host_key, top_frame_site_key, name, path, source_scheme, source_port));
+";
+
+fn open_cookies_db(path: &Path) -> Result<Connection, String> {
+    let fresh = !path.exists();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("create cookies dir failed: {e}"))?;
+    }
+    let conn = Connection::open(path).map_err(|e| format!("open cookies db failed: {e}"))?;
+    if fresh {
+        conn.execute_batch(COOKIES_SCHEMA_V21)
+            .map_err(|e| format!("init cookies db failed: {e}"))?;
+    }
+    Ok(conn)
+}
+
+fn table_columns(conn: &Connection) -> Result<Vec<(String, String)>, String> {
+    let mut stmt = conn
+        .prepare("PRAGMA table_info(cookies)")
+        .map_err(|e| format!("read cookies schema failed: {e}"))?;
+    let rows = stmt
+        .query_map([], |r| Ok((r.get::<_, String>(1)?, r.get::<_, String>(2)?)))
+        .map_err(|e| format!("read cookies schema failed: {e}"))?;
+    Ok(rows.flatten().collect())
+}
+
+fn cookie_column_value(c: &Cookie, column: &str, now: i64) -> Option<rusqlite::types::Value> {
+    use rusqlite::types::Value as V;
+    let v = match column {
+        "creation_utc" | "last_access_utc" | "last_update_utc" => V::Integer(now),
+        "host_key" => V::Text(c.domain.clone()),
+        "top_frame_site_key" => V::Text(String::new()),
+        "name" => V::Text(c.name.clone()),
+        // 明文写入 value、encrypted_value 留空，Chromium 读取时会直接使用 value
+        "value" => V::Text(c.value.clone()),
+        "encrypted_value" => V::Blob(Vec::new()),
+        "path" => V::Text(c.path.clone()),
+        "expires_utc" => V::Integer(c.expires.map(to_chrome_time).unwrap_or(0)),
+        "is_secure" => V::Integer(c.secure as i64),
+        "is_httponly" => V::Integer(c.http_only as i64),
+        "has_expires" | "is_persistent" => V::Integer(c.expires.is_some() as i64),
+        "priority" => V::Integer(1),
+        "samesite" => V::Integer(same_site_to_db(c.same_site)),
+        "source_scheme" => V::Integer(if c.secure { 2 } else { 1 }),
+        "source_port" => V::Integer(if c.secure { 443 } else { 80 }),
+        _ => return None,
+    };
+    Some(v)
+}
+
+fn write_cookies_db(path: &Path, cookies: &[Cookie]) -> Result<usize, String> {
+    let mut conn = open_cookies_db(path)?;
+    let columns = table_columns(&conn)?;
+    let now = to_chrome_time(chrono::Utc::now().timestamp());
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("begin cookies tx failed: {e}"))?;
+    let sql = format!(
+        "INSERT OR REPLACE INTO cookies ({}) VALUES ({})",
+        columns
+            .iter()
+            .map(|(n, _)| format!("\"{}\"", n))
+            .collect::<Vec<_>>()
+            .join(","),
+        vec!["?"; columns.len()].join(",")
+    );
+    let mut written = 0;
+    {
+        let mut stmt = tx
+            .prepare(&sql)
+            .map_err(|e| format!("prepare cookie insert failed: {e}"))?;
+        for c in cookies {
+            // 未知的新列按类型填默认值，兼容更新的数据库版本
+            let values = columns.iter().map(|(name, ty)| {
+                cookie_column_value(c, name, now).unwrap_or_else(|| {
+                    if ty.eq_ignore_ascii_case("INTEGER") {
+                        rusqlite::types::Value::Integer(0)
+                    } else if ty.eq_ignore_ascii_case("BLOB") {
+                        rusqlite::types::Value::Blob(Vec::new())
+                    } else {
+                        rusqlite::types::Value::Text(String::new())
+                    }
+                })
+            });
+            stmt.execute(params_from_iter(values))
+                .map_err(|e| format!("insert cookie {} failed: {e}", c.name))?;
+            written += 1;
+        }
+    }
+    tx.commit()
+        .map_err(|e| format!("commit cookies failed: {e}"))?;
+    Ok(written)
+}
+
+// macOS 使用 --use-mock-keychain、Linux 使用 --password-store=basic 时，v10 密文的口令固定
+fn v10_password() -> Option<(&'static [u8], u32)> {
+    if cfg!(target_os = "macos") {
+        Some((b"mock_password", 1003))
+    } else if cfg!(target_os = "linux") {
+        Some((b"peanuts", 1))
+    } else {
+        None
+    }
+}
+
+pub(crate) fn decrypt_v10(
+    data: &[u8],
+    password: &[u8],
+    iterations: u32,
+    strip_domain_hash: bool,
+) -> Option<String> {
+    let body = data.strip_prefix(b"v10")?;
+    let mut key = [0u8; 16];
+    pbkdf2::pbkdf2_hmac::<sha1::Sha1>(password, b"saltysalt", iterations, &mut key);
+    let iv = [b' '; 16];
+    let mut buf = body.to_vec();
+    let plain = cbc::Decryptor::<aes::Aes128>::new(&key.into(), &iv.into())
+        .decrypt_padded_mut::<Pkcs7>(&mut buf)
+        .ok()?;
+    // 数据库 v24 起明文前附加 32 字节的 host_key SHA256
+    let plain = if strip_domain_hash && plain.len() >= 32 {
+        &plain[32..]
+    } else {
+        plain
+    };
+    String::from_utf8(plain.to_vec()).ok()
+}
+
+fn db_version(conn: &Connection) -> i64 {
+    conn.query_row("SELECT value FROM meta WHERE key='version'", [], |r| {
+        r.get::<_, String>(0)
+    })
+    .ok()
+    .and_then(|v| v.parse().ok())
+    .unwrap_or(0)
+}
+
+fn read_cookies_db(path: &Path) -> Result<(Vec<Cookie>, Vec<String>), String> {
+    if !path.exists() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+    let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("open cookies db failed: {e}"))?;
+    let strip_hash = db_version(&conn) >= 24;
+    let mut stmt = conn
+        .prepare(
+            "SELECT host_key, name, value, encrypted_value, path, expires_utc, is_secure, \
+             is_httponly, has_expires, samesite FROM cookies",
+        )
+        .map_err(|e| format!("query cookies failed: {e}"))?;
+    let rows = stmt
+        .query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, Vec<u8>>(3)?,
+                r.get::<_, String>(4)?,
+                r.get::<_, i64>(5)?,
+                r.get::<_, bool>(6)?,
+                r.get::<_, bool>(7)?,
+                r.get::<_, bool>(8)?,
+                r.get::<_, i64>(9)?,
+            ))
+        })
+        .map_err(|e| format!("query cookies failed: {e}"))?;
+    let mut cookies = Vec::new();
+    let mut warnings = Vec::new();
+    for row in rows.flatten() {
+        let (domain, name, value, encrypted, path, expires, secure, http_only, has_exp, ss) = row;
+        let value = if encrypted.is_empty() {
+            value
+        } else if encrypted.starts_with(b"v11") {
+            // Linux 上口令保存在系统钥匙环中，无法离线读取
+            warnings.push(format!(
+                "cookie {} on {} is encrypted with the system keyring (v11), skipped",
+                name, domain
+            ));
+            continue;
+        } else {
+            match v10_password().and_then(|(pw, it)| decrypt_v10(&encrypted, pw, it, strip_hash)) {
+                Some(v) => v,
+                None => {
+                    warnings.push(format!("cannot decrypt cookie {} on {}", name, domain));
+                    continue;
+                }
+            }
+        };
+        cookies.push(Cookie {
+            name,
+            value,
+            domain,
+            path,
+            expires: has_exp.then(|| from_chrome_time(expires)),
+            secure,
+            http_only,
+            same_site: same_site_from_db(ss),
+        });
+    }
+    Ok((cookies, warnings))
+}
+
+// 加密保存且未启动的 profile 没有可读写的明文数据库
+fn ensure_not_sealed(label: &str) -> Result<(), String> {
+    if vault::is_sealed(label) && vault::user_data_dir(label) == crate::profile_dir_path(label) {
+        return Err(format!(
+            "profile {} is sealed in the vault, open it to manage cookies",
+            label
+        ));
+    }
+    Ok(())
+}
+
+fn cdp_cookie_param(c: &Cookie) -> Value {
+    let mut v = json!({
+        "name": c.name,
+        "value": c.value,
+        "path": c.path,
+        "secure": c.secure,
+        "httpOnly": c.http_only,
+    });
+    if c.domain.starts_with('.') {
+        v["domain"] = json!(c.domain);
+    } else {
+        // host-only cookie 需通过 url 设置，传 domain 会变成域 cookie
+        let scheme = if c.secure { "https" } else { "http" };
+        v["url"] = json!(format!("{}://{}{}", scheme, c.domain, c.path));
+    }
+    if let Some(e) = c.expires {
+        v["expires"] = json!(e);
+    }
+    match c.same_site {
+        SameSite::NoRestriction => v["sameSite"] = json!("None"),
+        SameSite::Lax => v["sameSite"] = json!("Lax"),
+        SameSite::Strict => v["sameSite"] = json!("Strict"),
+        SameSite::Unspecified => {}
+    }
+    v
+}
+
+fn cookie_from_cdp(v: &Value) -> Option<Cookie> {
+    let s = |k: &str| v.get(k).and_then(|x| x.as_str()).map(|x| x.to_string());
+    let b = |k: &str| v.get(k).and_then(|x| x.as_bool()).unwrap_or(false);
+    Some(Cookie {
+        name: s("name")?,
+        value: s("value").unwrap_or_default(),
+        domain: s("domain")?,
+        path: s("path").unwrap_or_else(|| "/".into()),
+        expires: if b("session") {
+            None
+        } else {
+            v.get("expires").and_then(|e| e.as_f64()).map(|e| e as i64)
+        },
+        secure: b("secure"),
+        http_only: b("httpOnly"),
+        same_site: parse_same_site(v.get("sameSite")),
+    })
+}
+
+// Network 域需在页面会话中调用；没有打开的页面时新建一个空白页
+fn page_session(client: &mut CdpClient) -> Result<String, String> {
+    let targets = client.call("Target.getTargets", json!({}))?;
+    let page = targets["targetInfos"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|t| t["type"] == "page")
+        .map(|t| t["targetId"].clone());
+    let target_id = match page {
+        Some(id) => id,
+        None => {
+            client.call("Target.createTarget", json!({ "url": "about:blank" }))?["targetId"].clone()
+        }
+    };
+    let attached = client.call(
+        "Target.attachToTarget",
+        json!({ "targetId": target_id, "flatten": true }),
+    )?;
+    attached["sessionId"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "attach to page failed: no session id".to_string())
+}
+
+#[tauri::command]
+pub(crate) fn profile_cookies_import(
+    label: &str,
+    file: &str,
+    format: CookieFormat,
+) -> Result<CookieImportSummary, String> {
+    let content = fs::read_to_string(file).map_err(|e| format!("read cookie file failed: {e}"))?;
+    let (cookies, warnings) = parse_cookies(&content, format)?;
+    let skipped = warnings.len();
+    let (imported, via) = if browser_running(label).is_some() {
+        let mut client = CdpClient::for_label(label)?;
+        let params: Vec<Value> = cookies.iter().map(cdp_cookie_param).collect();
+        let session = page_session(&mut client)?;
+        client.call_in(
+            Some(&session),
+            "Network.setCookies",
+            json!({ "cookies": params }),
+        )?;
+        (cookies.len(), "cdp")
+    } else {
+        ensure_not_sealed(label)?;
+        (
+            write_cookies_db(&cookies_db_path(label), &cookies)?,
+            "database",
+        )
+    };
+    write_log(
+        "INFO",
+        &format!(
+            "profile_cookies_import label={} format={:?} via={} imported={} skipped={}",
+            label, format, via, imported, skipped
+        ),
+    );
+    Ok(CookieImportSummary {
+        imported,
+        skipped,
+        via: via.into(),
+        warnings,
+    })
+}
+
+// 返回导出的文本内容，由前端决定保存位置
+#[tauri::command]
+pub(crate) fn profile_cookies_export(
+    label: &str,
+    format: CookieFormat,
+) -> Result<CookieExport, String> {
+    let (cookies, warnings) = if browser_running(label).is_some() {
+        let mut client = CdpClient::for_label(label)?;
+        let res = client.call("Storage.getCookies", json!({}))?;
+        let cookies = res
+            .get("cookies")
+            .and_then(|c| c.as_array())
+            .map(|a| a.iter().filter_map(cookie_from_cdp).collect())
+            .unwrap_or_default();
+        (cookies, Vec::new())
+    } else {
+        ensure_not_sealed(label)?;
+        read_cookies_db(&cookies_db_path(label))?
+    };
+    for w in &warnings {
+        write_log(
+            "WARN",
+            &format!("profile_cookies_export label={} {}", label, w),
+        );
+    }
+    write_log(
+        "INFO",
+        &format!(
+            "profile_cookies_export label={} format={:?} count={} skipped={}",
+            label,
+            format,
+            cookies.len(),
+            warnings.len()
+        ),
+    );
+    Ok(CookieExport {
+        content: format_cookies(&cookies, format),
+        exported: cookies.len(),
+        skipped: warnings.len(),
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::cipher::BlockEncryptMut;
+
+    #[test]
+    fn test_netscape_round_trip() {
+        let txt = "# Netscape HTTP Cookie File\n\
+                   .example.com\tTRUE\t/\tTRUE\t1893456000\tsid\tabc\n\
+                   #HttpOnly_login.example.com\tFALSE\t/app\tFALSE\t0\ttoken\tx\ty\n\
+                   bad line\n";
+        let (cookies, warnings) = parse_netscape(txt);
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(cookies[0].domain, ".example.com");
+        assert_eq!(cookies[0].expires, Some(1_893_456_000));
+        assert!(cookies[1].http_only);
+        assert_eq!(cookies[1].expires, None);
+        assert_eq!(cookies[1].value, "x\ty");
+        let (again, _) = parse_netscape(&to_netscape(&cookies));
+        assert_eq!(again, cookies);
+    }
+
+    #[test]
+    fn test_json_formats_and_round_trip() {
+        let json = r#"[
+            {"domain":".example.com","hostOnly":false,"name":"a","value":"1","path":"/",
+             "expirationDate":1893456000.5,"secure":true,"httpOnly":true,"sameSite":"no_restriction"},
+            {"domain":"shop.example.com","name":"b","value":"2","expires":-1,"session":true,"sameSite":"Lax"},
+            {"value":"orphan"}
+        ]"#;
+        let (cookies, warnings) = parse_json(json).unwrap();
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(cookies[0].expires, Some(1_893_456_000));
+        assert_eq!(cookies[0].same_site, SameSite::NoRestriction);
+        assert_eq!(cookies[1].domain, "shop.example.com");
+        assert_eq!(cookies[1].expires, None);
+        assert_eq!(cookies[1].same_site, SameSite::Lax);
+        let (again, _) = parse_json(&to_json(&cookies)).unwrap();
+        assert_eq!(again, cookies);
+    }
+
+    #[test]
+    fn test_chrome_time_and_v10_decrypt() {
+        assert_eq!(
+            from_chrome_time(to_chrome_time(1_700_000_000)),
+            1_700_000_000
+        );
+        let mut key = [0u8; 16];
+        pbkdf2::pbkdf2_hmac::<sha1::Sha1>(b"peanuts", b"saltysalt", 1, &mut key);
+        let plain = b"hello";
+        let mut buf = [0u8; 16];
+        let ct = cbc::Encryptor::<aes::Aes128>::new(&key.into(), &[b' '; 16].into())
+            .encrypt_padded_b2b_mut::<Pkcs7>(plain, &mut buf)
+            .unwrap();
+        let mut data = b"v10".to_vec();
+        data.extend_from_slice(ct);
+        assert_eq!(
+            decrypt_v10(&data, b"peanuts", 1, false).as_deref(),
+            Some("hello")
+        );
+        assert_eq!(decrypt_v10(b"v11abc", b"peanuts", 1, false), None);
+    }
+
+    #[test]
+    fn test_cookies_db_write_then_read() {
+        let dir = std::env::temp_dir().join(format!("libre-cookie-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("Network").join("Cookies");
+        let mut c = Cookie {
+            name: "sid".into(),
+            value: "v".into(),
+            domain: ".example.com".into(),
+            path: "/".into(),
+            expires: Some(1_893_456_000),
+            secure: true,
+            http_only: false,
+            same_site: SameSite::Lax,
+        };
+        assert_eq!(write_cookies_db(&path, &[c.clone()]).unwrap(), 1);
+        // 同一 cookie 再次导入时覆盖
+        c.value = "v2".into();
+        write_cookies_db(&path, &[c.clone()]).unwrap();
+        let (read, warnings) = read_cookies_db(&path).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(read, vec![c.clone()]);
+
+        // 钥匙环加密的 cookie 跳过并给出原因
+        let keyring = Cookie {
+            name: "kr".into(),
+            ..c.clone()
+        };
+        write_cookies_db(&path, &[keyring]).unwrap();
+        Connection::open(&path)
+            .unwrap()
+            .execute(
+                "UPDATE cookies SET value = '', encrypted_value = x'7631316162' WHERE name = 'kr'",
+                [],
+            )
+            .unwrap();
+        let (read, warnings) = read_cookies_db(&path).unwrap();
+        assert_eq!(read, vec![c]);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("v11"), "{}", warnings[0]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
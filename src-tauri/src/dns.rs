@@ -0,0 +1,347 @@
+// 按 profile 的 DNS 设置：经 SOCKS 代理远程解析、DoH，或自定义解析规则，防止经本机解析泄露
+use crate::profile_store::{self, ProfileConfig};
+use crate::proxies::ProxyRef;
+use crate::write_log;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsMode {
+    // 使用系统解析
+    System,
+    // 由 SOCKS5 代理解析，本机解析一律失败
+    Remote,
+    // 强制 DNS over HTTPS
+    Doh,
+    // 自定义 host resolver 规则
+    Rules,
+}
+
+// profile.json 中的 dns 字段；mode 未设置时按代理协议自动选择
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DnsConfig {
+    pub mode: Option<DnsMode>,
+    pub doh_template: Option<String>,
+    // 如 "MAP *.test 127.0.0.1"、"EXCLUDE localhost"
+    pub resolver_rules: Vec<String>,
+}
+
+// (协议, 主机)；proxy:/pool: 引用须先解析为地址
+fn proxy_parts(proxy: Option<&str>) -> Result<Option<(String, String)>, String> {
+    let Some(raw) = proxy.map(str::trim).filter(|p| !p.is_empty()) else {
+        return Ok(None);
+    };
+    if ProxyRef::parse(raw).is_some() {
+        return Err(format!("proxy reference {} must be resolved first", raw));
+    }
+    Ok(parse_parts(raw))
+}
+
+fn parse_parts(raw: &str) -> Option<(String, String)> {
+    let url = url::Url::parse(raw).ok()?;
+    Some((
+        url.scheme().to_ascii_lowercase(),
+        url.host_str()?.to_string(),
+    ))
+}
+
+fn is_socks(scheme: &str) -> bool {
+    matches!(scheme, "socks5" | "socks5h")
+}
+
+// SOCKS5 代理默认远程解析，其他情况使用系统解析
+pub(crate) fn effective_mode(dns: &DnsConfig, proxy: Option<&str>) -> DnsMode {
+    dns.mode.unwrap_or_else(|| match proxy_parts(proxy) {
+        Ok(Some((scheme, _))) if is_socks(&scheme) => DnsMode::Remote,
+        _ => DnsMode::System,
+    })
+}
+
+// doh 模式下返回模板，供生成 DnsOverHttps* 策略
+pub(crate) fn doh_template(dns: &DnsConfig) -> Result<Option<String>, String> {
+    if dns.mode != Some(DnsMode::Doh) {
+        return Ok(None);
+    }
+    let template = dns
+        .doh_template
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .ok_or("DoH mode requires a template")?;
+    if !template.starts_with("https://") {
+        return Err(format!("DoH template must be an https url: {}", template));
+    }
+    Ok(Some(template.to_string()))
+}
+
+// 每条规则为 MAP <pattern> <target> 或 EXCLUDE <pattern>
+fn compile_rule(rule: &str) -> Result<String, String> {
+    let parts: Vec<&str> = rule.split_whitespace().collect();
+    if rule.contains(',') {
+        return Err(format!("resolver rule must not contain ',': {}", rule));
+    }
+    match parts.as_slice() {
+        [kw, pattern, target] if kw.eq_ignore_ascii_case("map") => {
+            Ok(format!("MAP {} {}", pattern, target))
+        }
+        [kw, pattern] if kw.eq_ignore_ascii_case("exclude") => Ok(format!("EXCLUDE {}", pattern)),
+        _ => Err(format!("invalid resolver rule: {}", rule)),
+    }
+}
+
+// server 为浏览器实际连接的代理（--proxy-server），经本地中转时与上游代理不同
+pub(crate) fn launch_args(
+    dns: &DnsConfig,
+    proxy: Option<&str>,
+    server: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let parts = proxy_parts(proxy)?;
+    match effective_mode(dns, proxy) {
+        DnsMode::System => Ok(Vec::new()),
+        DnsMode::Doh => doh_template(dns).map(|_| Vec::new()),
+        DnsMode::Remote => {
+            let Some((scheme, host)) = parts.filter(|(s, _)| is_socks(s)) else {
+                return Err("remote DNS requires a socks5 or socks5h proxy".into());
+            };
+            write_log(
+                "INFO",
+                &format!("dns remote via {} proxy host={}", scheme, host),
+            );
+            // 主机名交给代理解析；本机解析全部返回失败，浏览器要连接的代理地址除外。
+            // 经中转时浏览器连的是 127.0.0.1，不排除它中转就不可达
+            let exclude = server.and_then(parse_parts).map(|(_, h)| h).unwrap_or(host);
+            Ok(vec![format!(
+                "--host-resolver-rules=MAP * ~NOTFOUND , EXCLUDE {}",
+                exclude
+            )])
+        }
+        DnsMode::Rules => {
+            let rules = dns
+                .resolver_rules
+                .iter()
+                .map(|r| r.trim())
+                .filter(|r| !r.is_empty())
+                .map(compile_rule)
+                .collect::<Result<Vec<_>, _>>()?;
+            if rules.is_empty() {
+                return Err("resolver rules mode requires at least one rule".into());
+            }
+            Ok(vec![format!("--host-resolver-rules={}", rules.join(" , "))])
+        }
+    }
+}
+
+// DoH 没有对应的命令行参数，策略又只有非官方构建识别，因此写入 Local State 的 dns_over_https.*；
+// 其他模式下移除，恢复浏览器默认
+pub(crate) fn apply_local_state(
+    user_data_dir: &Path,
+    template: Option<&str>,
+) -> Result<(), String> {
+    let path = user_data_dir.join("Local State");
+    let mut state: Value = fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .filter(|v: &Value| v.is_object())
+        .unwrap_or_else(|| json!({}));
+    match template {
+        Some(t) => {
+            state["dns_over_https"] = json!({ "mode": "secure", "templates": t });
+        }
+        None => {
+            let removed = state
+                .as_object_mut()
+                .and_then(|m| m.remove("dns_over_https"));
+            if removed.is_none() {
+                return Ok(());
+            }
+        }
+    }
+    fs::create_dir_all(user_data_dir).map_err(|e| format!("create profile dir failed: {e}"))?;
+    let content =
+        serde_json::to_string(&state).map_err(|e| format!("serialize local state failed: {e}"))?;
+    fs::write(&path, content).map_err(|e| format!("write local state failed: {e}"))
+}
+
+// 启动前调用：DNS 设置与代理协议不符时拒绝启动；
+// proxy 为解析后的上游代理地址，server 为交给浏览器的 --proxy-server
+pub(crate) fn prepare_launch(
+    label: &str,
+    user_data_dir: &Path,
+    proxy: Option<&str>,
+    server: Option<&str>,
+) -> Result<Vec<String>, String> {
+    let config: ProfileConfig = profile_store::load(label).unwrap_or_default();
+    let args = launch_args(&config.dns, proxy, server)
+        .map_err(|e| format!("profile {} dns invalid: {}", label, e))?;
+    apply_local_state(user_data_dir, doh_template(&config.dns)?.as_deref())?;
+    write_log(
+        "INFO",
+        &format!(
+            "dns label={} mode={:?}",
+            label,
+            effective_mode(&config.dns, proxy)
+        ),
+    );
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy;
+    use crate::proxies::{ProxyEntry, ProxyScheme};
+
+    #[test]
+    fn test_mode_defaults_and_validation() {
+        let auto = DnsConfig::default();
+        assert_eq!(
+            effective_mode(&auto, Some("socks5://u:p@10.0.0.1:1080")),
+            DnsMode::Remote
+        );
+        assert_eq!(
+            effective_mode(&auto, Some("http://10.0.0.1:8080")),
+            DnsMode::System
+        );
+        assert_eq!(effective_mode(&auto, None), DnsMode::System);
+        assert_eq!(
+            launch_args(&auto, Some("socks5h://proxy.example:1080"), None).unwrap(),
+            vec!["--host-resolver-rules=MAP * ~NOTFOUND , EXCLUDE proxy.example"]
+        );
+        assert!(launch_args(&auto, Some("http://10.0.0.1:8080"), None)
+            .unwrap()
+            .is_empty());
+
+        let remote = DnsConfig {
+            mode: Some(DnsMode::Remote),
+            ..Default::default()
+        };
+        assert!(launch_args(&remote, Some("http://10.0.0.1:8080"), None).is_err());
+        assert!(launch_args(&remote, None, None).is_err());
+        // 显式指定系统解析时不加规则
+        let system = DnsConfig {
+            mode: Some(DnsMode::System),
+            ..Default::default()
+        };
+        assert!(launch_args(&system, Some("socks5://10.0.0.1:1080"), None)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_library_proxy_reference() {
+        let auto = DnsConfig::default();
+        // 未解析的库引用不能当作系统解析处理
+        assert!(launch_args(&auto, Some("proxy:PX-1"), None).is_err());
+        let entry = ProxyEntry {
+            id: "PX-1".into(),
+            scheme: ProxyScheme::Socks5,
+            host: "10.0.0.2".into(),
+            port: 1080,
+            username: Some("u".into()),
+            password: Some("p".into()),
+            ..Default::default()
+        };
+        let url = entry.url_for("CHE-1", "s1").unwrap();
+        assert_eq!(
+            launch_args(&auto, Some(&url), None).unwrap(),
+            vec!["--host-resolver-rules=MAP * ~NOTFOUND , EXCLUDE 10.0.0.2"]
+        );
+        let remote = DnsConfig {
+            mode: Some(DnsMode::Remote),
+            ..Default::default()
+        };
+        assert!(launch_args(&remote, Some(&url), None).is_ok());
+    }
+
+    // 默认开启中转时浏览器连 127.0.0.1，排除的必须是它而不是上游 SOCKS 主机
+    #[test]
+    fn test_remote_dns_behind_relay() {
+        let auto = DnsConfig::default();
+        let args = launch_args(
+            &auto,
+            Some("socks5://u:p@10.0.0.3:1080"),
+            Some("http://127.0.0.1:41234"),
+        )
+        .unwrap();
+        assert_eq!(
+            args,
+            vec!["--host-resolver-rules=MAP * ~NOTFOUND , EXCLUDE 127.0.0.1"]
+        );
+        // 不经中转时 server 即上游代理
+        assert_eq!(
+            launch_args(
+                &auto,
+                Some("socks5://10.0.0.3:1080"),
+                Some("socks5://10.0.0.3:1080")
+            )
+            .unwrap(),
+            vec!["--host-resolver-rules=MAP * ~NOTFOUND , EXCLUDE 10.0.0.3"]
+        );
+    }
+
+    #[test]
+    fn test_rules_and_doh_rendering() {
+        let rules = DnsConfig {
+            mode: Some(DnsMode::Rules),
+            resolver_rules: vec![
+                "map *.test 127.0.0.1".into(),
+                " ".into(),
+                "EXCLUDE localhost".into(),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            launch_args(&rules, None, None).unwrap(),
+            vec!["--host-resolver-rules=MAP *.test 127.0.0.1 , EXCLUDE localhost"]
+        );
+        for bad in ["MAP *.test", "DROP x", "MAP a b, MAP c d"] {
+            let cfg = DnsConfig {
+                resolver_rules: vec![bad.into()],
+                ..rules.clone()
+            };
+            assert!(launch_args(&cfg, None, None).is_err(), "{}", bad);
+        }
+
+        let mut cfg = ProfileConfig {
+            dns: DnsConfig {
+                mode: Some(DnsMode::Doh),
+                doh_template: Some("https://dns.example/dns-query{?dns}".into()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let built = policy::build(&cfg).unwrap();
+        assert_eq!(built["DnsOverHttpsMode"], "secure");
+        assert_eq!(
+            built["DnsOverHttpsTemplates"],
+            "https://dns.example/dns-query{?dns}"
+        );
+        let dir = std::env::temp_dir().join(format!("libre-dns-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Local State"), r#"{"browser":{"x":1}}"#).unwrap();
+        apply_local_state(&dir, doh_template(&cfg.dns).unwrap().as_deref()).unwrap();
+        let state: Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("Local State")).unwrap()).unwrap();
+        assert_eq!(state["dns_over_https"]["mode"], "secure");
+        assert_eq!(
+            state["dns_over_https"]["templates"],
+            "https://dns.example/dns-query{?dns}"
+        );
+        assert_eq!(state["browser"]["x"], 1);
+        apply_local_state(&dir, None).unwrap();
+        let state: Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("Local State")).unwrap()).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert!(state.get("dns_over_https").is_none());
+
+        cfg.policies.doh_mode = Some("automatic".into());
+        assert!(policy::build(&cfg).is_err());
+        cfg.policies.doh_mode = None;
+        cfg.dns.doh_template = Some("http://dns.example/".into());
+        assert!(policy::build(&cfg).is_err());
+    }
+}
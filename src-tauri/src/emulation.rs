@@ -0,0 +1,364 @@
+// 运行中 profile 的 CDP 覆盖（时区、地理位置、网络条件）：每个 profile 一条常驻连接
+// 这些覆盖只在设置它的会话存续期间有效，会话断开后 Chromium 即撤销，因此连接保持到浏览器退出；
+// 通过自动附加覆盖之后新开的页面与 iframe，新目标在覆盖生效前保持暂停；
+// 已生效的覆盖记录在 emulation.json，管理器重启接管浏览器后据此重放
+use crate::cdp::{Attached, CdpClient};
+use crate::{profile_dir_path, write_log};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+// 连接断开后（如自动重启）等待浏览器恢复的时长
+const RECONNECT_WINDOW: Duration = Duration::from_secs(60);
+
+static WORKERS: OnceCell<Mutex<HashMap<String, Worker>>> = OnceCell::new();
+
+// 要下发的命令，按顺序执行
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Overrides {
+    // 浏览器级命令，每次建立连接后执行一次
+    pub browser: Vec<(String, Value)>,
+    // 对每个页面与 iframe 会话执行
+    pub session: Vec<(String, Value)>,
+}
+
+impl Overrides {
+    fn extend(&mut self, other: &Overrides) {
+        self.browser.extend(other.browser.iter().cloned());
+        self.session.extend(other.session.iter().cloned());
+    }
+}
+
+struct Request {
+    overrides: Overrides,
+    reply: mpsc::Sender<Result<(), String>>,
+}
+
+struct Worker {
+    tx: mpsc::Sender<Request>,
+    stop: Arc<AtomicBool>,
+}
+
+fn workers() -> &'static Mutex<HashMap<String, Worker>> {
+    WORKERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn record_path(label: &str) -> PathBuf {
+    profile_dir_path(label).join("emulation.json")
+}
+
+fn write_record(path: &Path, overrides: &Overrides) {
+    let res = serde_json::to_string(overrides)
+        .map_err(|e| e.to_string())
+        .and_then(|s| fs::write(path, s).map_err(|e| e.to_string()));
+    if let Err(e) = res {
+        write_log(
+            "WARN",
+            &format!("write emulation record {} failed: {}", path.display(), e),
+        );
+    }
+}
+
+fn read_record(path: &Path) -> Option<Result<Overrides, String>> {
+    let content = fs::read_to_string(path).ok()?;
+    Some(serde_json::from_str(&content).map_err(|e| format!("parse emulation record failed: {e}")))
+}
+
+// 浏览器刚启动时调试端口可能尚未就绪
+fn connect(label: &str) -> Result<CdpClient, String> {
+    let mut last = String::new();
+    for _ in 0..50 {
+        match CdpClient::for_label(label) {
+            Ok(c) => return Ok(c),
+            Err(e) => last = e,
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    Err(format!("devtools of {} not ready: {}", label, last))
+}
+
+fn run_all(
+    client: &mut CdpClient,
+    session: Option<&str>,
+    cmds: &[(String, Value)],
+) -> Result<(), String> {
+    for (method, params) in cmds {
+        client.call_in(session, method, params.clone())?;
+    }
+    Ok(())
+}
+
+struct Conn {
+    label: String,
+    client: CdpClient,
+    sessions: HashSet<String>,
+}
+
+impl Conn {
+    fn open(label: &str, overrides: &Overrides) -> Result<Self, String> {
+        Self::start(label, connect(label)?, overrides)
+    }
+
+    fn start(label: &str, mut client: CdpClient, overrides: &Overrides) -> Result<Self, String> {
+        run_all(&mut client, None, &overrides.browser)?;
+        client.auto_attach(None)?;
+        let mut conn = Self {
+            label: label.to_string(),
+            client,
+            sessions: HashSet::new(),
+        };
+        // 已打开的页面随即收到 attachedToTarget，处理完再返回
+        while let Some(ev) = conn.client.next_event(POLL_INTERVAL)? {
+            conn.handle(&ev, overrides);
+        }
+        Ok(conn)
+    }
+
+    fn handle(&mut self, ev: &Value, overrides: &Overrides) {
+        if ev["method"] == "Target.detachedFromTarget" {
+            if let Some(sid) = ev["params"]["sessionId"].as_str() {
+                self.sessions.remove(sid);
+            }
+            return;
+        }
+        let Some(attached) = Attached::from_event(ev) else {
+            return;
+        };
+        let sid = attached.session_id.clone();
+        if matches!(attached.target_type.as_str(), "page" | "iframe") {
+            let res = run_all(&mut self.client, Some(&sid), &overrides.session)
+                .and_then(|_| self.client.auto_attach(Some(&sid)));
+            match res {
+                Ok(()) => {
+                    self.sessions.insert(sid);
+                }
+                Err(e) => write_log(
+                    "WARN",
+                    &format!(
+                        "emulation attach failed label={} type={} err={}",
+                        self.label, attached.target_type, e
+                    ),
+                ),
+            }
+        }
+        if let Err(e) = self.client.resume(&attached) {
+            write_log(
+                "WARN",
+                &format!("emulation resume failed label={} err={}", self.label, e),
+            );
+        }
+    }
+
+    // 新增的覆盖对已附加的会话立即生效；已关闭的会话跳过
+    fn apply(&mut self, added: &Overrides) -> Result<(), String> {
+        run_all(&mut self.client, None, &added.browser)?;
+        let sessions: Vec<String> = self.sessions.iter().cloned().collect();
+        for sid in sessions {
+            if let Err(e) = run_all(&mut self.client, Some(&sid), &added.session) {
+                write_log(
+                    "WARN",
+                    &format!("emulation apply skipped label={} err={}", self.label, e),
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+fn run(label: String, first: Request, rx: mpsc::Receiver<Request>, stop: Arc<AtomicBool>) {
+    let mut overrides = first.overrides.clone();
+    let mut conn = match Conn::open(&label, &overrides) {
+        Ok(c) => {
+            write_record(&record_path(&label), &overrides);
+            let _ = first.reply.send(Ok(()));
+            Some(c)
+        }
+        Err(e) => {
+            let _ = first.reply.send(Err(e));
+            stop.store(true, Ordering::SeqCst);
+            None
+        }
+    };
+    let mut lost_at = None;
+    while !stop.load(Ordering::SeqCst) {
+        while let Ok(req) = rx.try_recv() {
+            let res = match conn.as_mut() {
+                Some(c) => c.apply(&req.overrides),
+                None => Err(format!("devtools of {} not connected", label)),
+            };
+            if res.is_ok() {
+                overrides.extend(&req.overrides);
+                write_record(&record_path(&label), &overrides);
+            }
+            let _ = req.reply.send(res);
+        }
+        let Some(c) = conn.as_mut() else {
+            if lost_at.is_some_and(|t: Instant| t.elapsed() > RECONNECT_WINDOW) {
+                break;
+            }
+            // 重启后调试端口会变化，需重新连接并重放全部覆盖
+            conn = Conn::open(&label, &overrides).ok();
+            if conn.is_some() {
+                write_log("INFO", &format!("emulation reconnected label={}", label));
+                lost_at = None;
+            }
+            continue;
+        };
+        match c.client.next_event(POLL_INTERVAL) {
+            Ok(Some(ev)) => c.handle(&ev, &overrides),
+            Ok(None) => {}
+            Err(e) => {
+                write_log(
+                    "WARN",
+                    &format!("emulation connection lost label={} err={}", label, e),
+                );
+                conn = None;
+                lost_at = Some(Instant::now());
+            }
+        }
+    }
+    // 先断开接收端，之后的 add 会另起连接
+    drop(rx);
+    if let Ok(mut m) = workers().lock() {
+        if m.get(&label).is_some_and(|w| Arc::ptr_eq(&w.stop, &stop)) {
+            m.remove(&label);
+        }
+    }
+}
+
+// 追加覆盖：首次调用时建立连接，之后复用；返回时已对现有页面生效
+pub(crate) fn add(label: &str, overrides: Overrides) -> Result<(), String> {
+    let (reply, result) = mpsc::channel();
+    let req = Request { overrides, reply };
+    {
+        let mut m = workers()
+            .lock()
+            .map_err(|_| "emulation lock poisoned".to_string())?;
+        let req = match m.get(label) {
+            Some(w) => match w.tx.send(req) {
+                Ok(()) => None,
+                Err(mpsc::SendError(req)) => Some(req),
+            },
+            None => Some(req),
+        };
+        if let Some(req) = req {
+            let (tx, rx) = mpsc::channel();
+            let stop = Arc::new(AtomicBool::new(false));
+            let flag = stop.clone();
+            let lbl = label.to_string();
+            thread::spawn(move || run(lbl, req, rx, flag));
+            m.insert(label.to_string(), Worker { tx, stop });
+        }
+    }
+    result
+        .recv_timeout(Duration::from_secs(30))
+        .map_err(|_| format!("emulation of {} timed out", label))?
+}
+
+// 浏览器最终退出时调用
+pub(crate) fn stop(label: &str) {
+    if let Some(w) = workers().lock().ok().and_then(|mut m| m.remove(label)) {
+        w.stop.store(true, Ordering::SeqCst);
+    }
+    let _ = fs::remove_file(record_path(label));
+}
+
+// 重新接管时调用：按记录重放全部覆盖；没有记录时返回 None
+pub(crate) fn resume(label: &str) -> Option<Result<(), String>> {
+    let overrides = match read_record(&record_path(label))? {
+        Ok(o) => o,
+        Err(e) => return Some(Err(e)),
+    };
+    Some(add(label, overrides))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::net::TcpListener;
+    use tungstenite::Message;
+
+    // 记录收到的命令；附加后再模拟一个新开的、等待调试器的页面
+    #[test]
+    fn test_overrides_follow_new_pages() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (seen_tx, seen) = mpsc::channel::<(Option<String>, String)>();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut ws = tungstenite::accept(stream).unwrap();
+            while let Ok(Message::Text(text)) = ws.read() {
+                let msg: Value = serde_json::from_str(&text).unwrap();
+                let method = msg["method"].as_str().unwrap_or_default().to_string();
+                let sid = msg["sessionId"].as_str().map(str::to_string);
+                let reply = json!({ "id": msg["id"], "result": {} });
+                ws.send(Message::Text(reply.to_string())).unwrap();
+                if method == "Target.setAutoAttach" && sid.is_none() {
+                    for (sid, waiting) in [("S1", false), ("S2", true)] {
+                        let ev = json!({ "method": "Target.attachedToTarget", "params": {
+                            "sessionId": sid, "waitingForDebugger": waiting,
+                            "targetInfo": { "targetId": sid, "type": "page" } } });
+                        ws.send(Message::Text(ev.to_string())).unwrap();
+                    }
+                }
+                let _ = seen_tx.send((sid, method));
+            }
+        });
+        let url = format!("ws://127.0.0.1:{}/devtools/browser/fake", port);
+        let overrides = Overrides {
+            browser: vec![("Browser.grantPermissions".into(), json!({}))],
+            session: vec![(
+                "Emulation.setTimezoneOverride".into(),
+                json!({ "timezoneId": "Asia/Tokyo" }),
+            )],
+        };
+        let conn = Conn::start("EMU-T1", CdpClient::connect(&url).unwrap(), &overrides).unwrap();
+        assert_eq!(conn.sessions.len(), 2);
+        let log: Vec<(Option<String>, String)> = seen.try_iter().collect();
+        let at = |sid: &str, m: &str| {
+            log.iter()
+                .position(|(s, x)| s.as_deref() == Some(sid) && x == m)
+        };
+        for sid in ["S1", "S2"] {
+            assert!(
+                at(sid, "Emulation.setTimezoneOverride").is_some(),
+                "{}",
+                sid
+            );
+            assert!(at(sid, "Target.setAutoAttach").is_some(), "{}", sid);
+        }
+        // 只有暂停的新页面需要 resume，且在覆盖之后
+        assert!(at("S1", "Runtime.runIfWaitingForDebugger").is_none());
+        assert!(
+            at("S2", "Emulation.setTimezoneOverride").unwrap()
+                < at("S2", "Runtime.runIfWaitingForDebugger").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("libre-emulation-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert!(read_record(&path).is_none());
+        let overrides = Overrides {
+            browser: Vec::new(),
+            session: vec![(
+                "Network.emulateNetworkConditions".into(),
+                json!({ "offline": false, "latency": 200 }),
+            )],
+        };
+        write_record(&path, &overrides);
+        assert_eq!(read_record(&path).unwrap().unwrap(), overrides);
+        let _ = fs::remove_file(&path);
+    }
+}
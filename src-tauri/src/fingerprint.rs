@@ -0,0 +1,368 @@
+// 指纹字段处理：前端以 JSON 保存（camelCase）；生成器基于内置的设备预设，给定种子时可复现
+use crate::list_installed_engines;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const PRESETS_JSON: &str = include_str!("fingerprint_presets.json");
+static PRESETS: OnceCell<Presets> = OnceCell::new();
+
+// 种子需在 JS Number 中无损往返
+const MAX_SEED: u64 = (1 << 53) - 1;
+
+#[derive(Debug, Deserialize)]
+struct Presets {
+    default_engine_major: u32,
+    os: HashMap<String, OsPreset>,
+    locales: Vec<LocalePreset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OsPreset {
+    platform: String,
+    ua_os: String,
+    screens: Vec<ScreenPreset>,
+    gpus: Vec<GpuPreset>,
+    hardware_concurrency: Vec<u32>,
+    device_memory: Vec<u32>,
+    fonts_base: Vec<String>,
+    fonts_extra: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScreenPreset {
+    width: u32,
+    height: u32,
+    pixel_ratios: Vec<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GpuPreset {
+    vendor: String,
+    renderer: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LocalePreset {
+    languages: String,
+    timezone: String,
+    // 有夏令时的时区偏移不固定，不填
+    timezone_offset: Option<i32>,
+}
+
+// 字段名与前端 fingerprint 表单一致
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedFingerprint {
+    pub os: String,
+    pub seed: u64,
+    pub user_agent: String,
+    pub platform: String,
+    pub languages: String,
+    pub timezone: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone_offset: Option<i32>,
+    pub screen: String,
+    pub screen_color_depth: u32,
+    pub screen_pixel_ratio: f64,
+    pub hardware_concurrency: u32,
+    pub device_memory: u32,
+    pub webgl_vendor: String,
+    pub webgl_renderer: String,
+    pub webgl_version: String,
+    pub canvas_noise: bool,
+    pub canvas_fingerprint: String,
+    pub audio_context: String,
+    pub audio_fingerprint: String,
+    pub font_fingerprint: String,
+    pub fonts: Vec<String>,
+}
+
+fn presets() -> &'static Presets {
+    PRESETS.get_or_init(|| {
+        serde_json::from_str(PRESETS_JSON).expect("embedded fingerprint presets are valid")
+    })
+}
+
+// 同一显卡家族内可互换的型号，按 WebGL renderer 中出现的型号名匹配
+pub(crate) const GPU_FAMILIES: &[(&str, &[&str])] = &[
+    (
+        "nvidia",
+        &[
+            "NVIDIA GeForce GTX 1050 Ti",
+            "NVIDIA GeForce GTX 1060 6GB",
+            "NVIDIA GeForce GTX 1650",
+            "NVIDIA GeForce GTX 1660 SUPER",
+            "NVIDIA GeForce RTX 2060",
+            "NVIDIA GeForce RTX 3060",
+            "NVIDIA GeForce RTX 3070",
+            "NVIDIA GeForce RTX 4060",
+        ],
+    ),
+    (
+        "amd",
+        &[
+            "AMD Radeon RX 570",
+            "AMD Radeon RX 580",
+            "AMD Radeon RX 5700 XT",
+            "AMD Radeon RX 6600",
+            "AMD Radeon RX 6700 XT",
+        ],
+    ),
+    (
+        "intel",
+        &[
+            "Intel(R) HD Graphics 520",
+            "Intel(R) HD Graphics 620",
+            "Intel(R) UHD Graphics 620",
+            "Intel(R) UHD Graphics 630",
+            "Intel(R) Iris(R) Xe Graphics",
+        ],
+    ),
+    (
+        "apple",
+        &[
+            "Apple M1",
+            "Apple M1 Pro",
+            "Apple M2",
+            "Apple M2 Pro",
+            "Apple M3",
+        ],
+    ),
+];
+
+// SplitMix64：给定种子时结果可复现
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(crate) fn from_entropy() -> Self {
+        Self(OsRng.next_u64())
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            return 0;
+        }
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub(crate) fn hex(&mut self, len: usize) -> String {
+        let mut s = String::with_capacity(len);
+        while s.len() < len {
+            s.push_str(&format!("{:016x}", self.next_u64()));
+        }
+        s.truncate(len);
+        s
+    }
+}
+
+fn pick<'a, T>(items: &'a [T], rng: &mut Rng) -> &'a T {
+    &items[rng.below(items.len())]
+}
+
+// 预设中该系统的全部字体
+pub(crate) fn os_fonts(os: &str) -> Option<Vec<&'static str>> {
+    let preset = presets().os.get(os)?;
+    Some(
+        preset
+            .fonts_base
+            .iter()
+            .chain(preset.fonts_extra.iter())
+            .map(String::as_str)
+            .collect(),
+    )
+}
+
+// 引擎主版本：优先指定版本，其次已安装的最新版本，最后用预设默认值
+pub(crate) fn engine_major(version: Option<&str>) -> u32 {
+    let parse = |v: &str| {
+        v.split('.')
+            .next()
+            .and_then(|m| m.trim().parse::<u32>().ok())
+    };
+    version
+        .and_then(parse)
+        .or_else(|| {
+            list_installed_engines()
+                .first()
+                .and_then(|e| parse(&e.version))
+        })
+        .unwrap_or(presets().default_engine_major)
+}
+
+pub(crate) fn generate(os: &str, seed: u64, major: u32) -> Result<GeneratedFingerprint, String> {
+    let preset = presets()
+        .os
+        .get(os)
+        .ok_or_else(|| format!("unsupported os: {} (expected windows, macos or linux)", os))?;
+    let mut rng = Rng::new(seed);
+    let screen = pick(&preset.screens, &mut rng);
+    let pixel_ratio = *pick(&screen.pixel_ratios, &mut rng);
+    let gpu = pick(&preset.gpus, &mut rng);
+    let locale = pick(&presets().locales, &mut rng);
+    let hardware_concurrency = *pick(&preset.hardware_concurrency, &mut rng);
+    let device_memory = *pick(&preset.device_memory, &mut rng);
+    let mut fonts = preset.fonts_base.clone();
+    for f in &preset.fonts_extra {
+        if rng.below(2) == 0 {
+            fonts.push(f.clone());
+        }
+    }
+    fonts.sort();
+    Ok(GeneratedFingerprint {
+        os: os.to_string(),
+        seed,
+        // 与 Chromium 的精简 UA 一致，只暴露主版本号
+        user_agent: format!(
+            "Mozilla/5.0 ({}) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/{}.0.0.0 Safari/537.36",
+            preset.ua_os, major
+        ),
+        platform: preset.platform.clone(),
+        languages: locale.languages.clone(),
+        timezone: locale.timezone.clone(),
+        timezone_offset: locale.timezone_offset,
+        screen: format!("{}x{}", screen.width, screen.height),
+        screen_color_depth: 24,
+        screen_pixel_ratio: pixel_ratio,
+        hardware_concurrency,
+        device_memory,
+        webgl_vendor: gpu.vendor.clone(),
+        webgl_renderer: gpu.renderer.clone(),
+        webgl_version: "WebGL 2.0".into(),
+        canvas_noise: true,
+        canvas_fingerprint: rng.hex(16),
+        audio_context: "random".into(),
+        audio_fingerprint: rng.hex(16),
+        font_fingerprint: "default".into(),
+        fonts,
+    })
+}
+
+#[tauri::command]
+pub(crate) fn fingerprint_generate(
+    os: &str,
+    seed: Option<u64>,
+    engine_version: Option<String>,
+) -> Result<GeneratedFingerprint, String> {
+    let seed = seed.unwrap_or_else(|| Rng::from_entropy().next_u64()) & MAX_SEED;
+    generate(os, seed, engine_major(engine_version.as_deref()))
+}
+
+// 返回 renderer 中匹配到的 (家族, 型号)，取最长匹配以区分 "Apple M1" 与 "Apple M1 Pro"
+pub(crate) fn gpu_model_in(renderer: &str) -> Option<(&'static str, &'static str)> {
+    GPU_FAMILIES
+        .iter()
+        .flat_map(|(family, models)| models.iter().map(move |m| (*family, *m)))
+        .filter(|(_, m)| renderer.contains(m))
+        .max_by_key(|(_, m)| m.len())
+}
+
+// 在同一家族内换一个型号，保留 ANGLE 等外层格式；无法识别时原样返回
+pub(crate) fn rerandomize_renderer(renderer: &str, rng: &mut Rng) -> String {
+    let Some((family, current)) = gpu_model_in(renderer) else {
+        return renderer.to_string();
+    };
+    let models = GPU_FAMILIES
+        .iter()
+        .find(|(f, _)| *f == family)
+        .map(|(_, m)| *m)
+        .unwrap_or(&[]);
+    let others: Vec<&str> = models.iter().copied().filter(|m| *m != current).collect();
+    if others.is_empty() {
+        return renderer.to_string();
+    }
+    renderer.replace(current, others[rng.below(others.len())])
+}
+
+// 重新生成带身份特征的字段：canvas 种子、音频噪声种子、WebGL renderer
+pub(crate) fn rerandomize_identity(fp: &mut Value, rng: &mut Rng) {
+    let Some(obj) = fp.as_object_mut() else {
+        return;
+    };
+    obj.insert("canvasFingerprint".into(), json!(rng.hex(16)));
+    let audio_blocked = obj.get("audioContext").and_then(|v| v.as_str()) == Some("blocked");
+    if !audio_blocked {
+        obj.insert("audioFingerprint".into(), json!(rng.hex(16)));
+    }
+    if let Some(renderer) = obj.get("webglRenderer").and_then(|v| v.as_str()) {
+        let next = rerandomize_renderer(renderer, rng);
+        obj.insert("webglRenderer".into(), json!(next));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_is_reproducible_and_consistent() {
+        for os in ["windows", "macos", "linux"] {
+            let a = generate(os, 42, 120).unwrap();
+            assert_eq!(a, generate(os, 42, 120).unwrap());
+            assert!(a.user_agent.contains("Chrome/120.0.0.0"));
+            let preset = &presets().os[os];
+            assert!(a.user_agent.contains(&preset.ua_os));
+            assert_eq!(a.platform, preset.platform);
+            assert!(preset.gpus.iter().any(|g| g.renderer == a.webgl_renderer));
+            assert!(preset.fonts_base.iter().all(|f| a.fonts.contains(f)));
+        }
+        let mac = generate("macos", 7, 120).unwrap();
+        assert!(mac.webgl_renderer.contains("Apple"));
+        assert_ne!(
+            generate("windows", 1, 120).unwrap().canvas_fingerprint,
+            generate("windows", 2, 120).unwrap().canvas_fingerprint
+        );
+        assert!(generate("android", 1, 120).is_err());
+    }
+
+    #[test]
+    fn test_rerandomize_keeps_gpu_family() {
+        let mut rng = Rng::new(7);
+        let renderer = "ANGLE (NVIDIA, NVIDIA GeForce RTX 3060 Direct3D11 vs_5_0 ps_5_0, D3D11)";
+        for _ in 0..20 {
+            let next = rerandomize_renderer(renderer, &mut rng);
+            assert_ne!(next, renderer);
+            assert!(next.starts_with("ANGLE (NVIDIA, NVIDIA GeForce"));
+            assert_eq!(gpu_model_in(&next).unwrap().0, "nvidia");
+        }
+        let apple = "ANGLE (Apple, ANGLE Metal Renderer: Apple M1 Pro, Unspecified Version)";
+        assert_eq!(gpu_model_in(apple), Some(("apple", "Apple M1 Pro")));
+        assert_eq!(rerandomize_renderer("SwiftShader", &mut rng), "SwiftShader");
+    }
+
+    #[test]
+    fn test_rerandomize_identity_fields() {
+        let mut fp = json!({
+            "userAgent": "ua",
+            "canvasFingerprint": "old",
+            "audioContext": "blocked",
+            "webglRenderer": "Intel(R) UHD Graphics 620"
+        });
+        rerandomize_identity(&mut fp, &mut Rng::new(1));
+        assert_eq!(fp["userAgent"], "ua");
+        assert_ne!(fp["canvasFingerprint"], "old");
+        assert_eq!(fp["canvasFingerprint"].as_str().unwrap().len(), 16);
+        assert!(fp.get("audioFingerprint").is_none());
+        assert_eq!(
+            gpu_model_in(fp["webglRenderer"].as_str().unwrap())
+                .unwrap()
+                .0,
+            "intel"
+        );
+    }
+}
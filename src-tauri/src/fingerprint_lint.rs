@@ -0,0 +1,459 @@
+// 指纹一致性检查：UA/platform/WebGL/字体需属于同一系统，数值需在浏览器可能返回的范围内
+use crate::fingerprint::{self, gpu_model_in};
+use crate::{profile_store, write_log};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// navigator.deviceMemory 只会返回这些值
+const DEVICE_MEMORY_VALUES: &[f64] = &[0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+// 早期创建界面提供过的选项，已保存的 profile 仍可启动，只提示修改
+const LEGACY_DEVICE_MEMORY_VALUES: &[f64] = &[16.0, 32.0];
+const KNOWN_OS: &[&str] = &["windows", "macos", "linux"];
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FingerprintIssue {
+    pub severity: Severity,
+    // 对应前端 fingerprint 对象中的字段
+    pub path: String,
+    pub code: String,
+    pub message: String,
+}
+
+// 代理出口的地理信息，缺省字段不参与检查
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct ProxyGeo {
+    pub country_code: Option<String>,
+    pub timezone: Option<String>,
+}
+
+fn issue(severity: Severity, path: &str, code: &str, message: String) -> FingerprintIssue {
+    FingerprintIssue {
+        severity,
+        path: path.to_string(),
+        code: code.to_string(),
+        message,
+    }
+}
+
+fn text<'a>(fp: &'a Value, key: &str) -> Option<&'a str> {
+    fp.get(key)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+// 前端表单里的数字可能以字符串保存
+fn number(fp: &Value, key: &str) -> Option<f64> {
+    match fp.get(key)? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+pub(crate) fn ua_os(ua: &str) -> Option<&'static str> {
+    if ua.contains("Windows NT") {
+        Some("windows")
+    } else if ua.contains("Android") {
+        Some("android")
+    } else if ua.contains("iPhone") || ua.contains("iPad") {
+        Some("ios")
+    } else if ua.contains("Macintosh") || ua.contains("Mac OS X") {
+        Some("macos")
+    } else if ua.contains("Linux") || ua.contains("X11") || ua.contains("CrOS") {
+        Some("linux")
+    } else {
+        None
+    }
+}
+
+fn platform_os(platform: &str) -> Option<&'static str> {
+    if platform.starts_with("Win") {
+        Some("windows")
+    } else if platform.starts_with("Mac") {
+        Some("macos")
+    } else if platform.starts_with("iPhone") || platform.starts_with("iPad") {
+        Some("ios")
+    } else if platform.starts_with("Linux") {
+        Some("linux")
+    } else {
+        None
+    }
+}
+
+// 只识别能明确判断系统的 ANGLE 后端
+fn renderer_os(renderer: &str) -> Option<&'static str> {
+    if renderer.contains("Direct3D") || renderer.contains("D3D11") || renderer.contains("D3D9") {
+        Some("windows")
+    } else if renderer.contains("Metal") || renderer.contains("Apple M") {
+        Some("macos")
+    } else if renderer.contains("Mesa") || renderer.contains("radeonsi") {
+        Some("linux")
+    } else {
+        None
+    }
+}
+
+fn chrome_major(ua: &str) -> Option<u32> {
+    let rest = &ua[ua.find("Chrome/")? + "Chrome/".len()..];
+    rest.split('.').next()?.parse().ok()
+}
+
+pub(crate) fn validate(
+    fp: &Value,
+    geo: Option<&ProxyGeo>,
+    engine_major: Option<u32>,
+) -> Vec<FingerprintIssue> {
+    use Severity::{Error, Warning};
+    let mut out = Vec::new();
+    let ua = text(fp, "userAgent");
+    let os = ua.and_then(ua_os);
+
+    if let (Some(os), Some(platform)) = (os, text(fp, "platform")) {
+        // Android 的 platform 同样以 Linux 开头
+        let p_os = platform_os(platform);
+        if p_os.is_some() && p_os != Some(os) && !(os == "android" && p_os == Some("linux")) {
+            out.push(issue(
+                Error,
+                "platform",
+                "platform_mismatch",
+                format!("platform {} does not match the {} user agent", platform, os),
+            ));
+        }
+    }
+    if let (Some(ua), Some(major)) = (ua, engine_major) {
+        if let Some(ua_major) = chrome_major(ua) {
+            if ua_major != major {
+                out.push(issue(
+                    Warning,
+                    "userAgent",
+                    "engine_version_mismatch",
+                    format!(
+                        "user agent claims Chrome {} but the engine is {}",
+                        ua_major, major
+                    ),
+                ));
+            }
+        }
+    }
+    if let Some(renderer) = text(fp, "webglRenderer") {
+        if let (Some(os), Some(r_os)) = (os, renderer_os(renderer)) {
+            if r_os != os {
+                out.push(issue(
+                    Error,
+                    "webglRenderer",
+                    "webgl_os_mismatch",
+                    format!(
+                        "WebGL renderer is a {} backend but the user agent is {}",
+                        r_os, os
+                    ),
+                ));
+            }
+        }
+        if let (Some(vendor), Some((family, _))) = (text(fp, "webglVendor"), gpu_model_in(renderer))
+        {
+            if !vendor.to_lowercase().contains(family) {
+                out.push(issue(
+                    Warning,
+                    "webglVendor",
+                    "webgl_vendor_mismatch",
+                    format!("WebGL vendor {} does not fit a {} renderer", vendor, family),
+                ));
+            }
+        }
+    }
+    if let Some(mem) = number(fp, "deviceMemory") {
+        if LEGACY_DEVICE_MEMORY_VALUES.contains(&mem) {
+            out.push(issue(
+                Warning,
+                "deviceMemory",
+                "device_memory_capped",
+                format!(
+                    "deviceMemory {} exceeds what browsers report, real devices show at most 8",
+                    mem
+                ),
+            ));
+        } else if !DEVICE_MEMORY_VALUES.contains(&mem) {
+            out.push(issue(
+                Error,
+                "deviceMemory",
+                "device_memory_invalid",
+                format!(
+                    "deviceMemory {} is impossible, browsers report one of 0.25, 0.5, 1, 2, 4, 8",
+                    mem
+                ),
+            ));
+        }
+    }
+    if let Some(cores) = number(fp, "hardwareConcurrency") {
+        if !(1.0..=128.0).contains(&cores) || cores.fract() != 0.0 {
+            out.push(issue(
+                Error,
+                "hardwareConcurrency",
+                "hardware_concurrency_invalid",
+                format!(
+                    "hardwareConcurrency {} is not a plausible core count",
+                    cores
+                ),
+            ));
+        }
+    }
+    if let Some(screen) = text(fp, "screen") {
+        match screen
+            .split_once('x')
+            .and_then(|(w, h)| Some((w.trim().parse::<u32>().ok()?, h.trim().parse::<u32>().ok()?)))
+        {
+            Some((w, h)) if w >= 640 && h >= 480 => {
+                if h > w {
+                    out.push(issue(
+                        Warning,
+                        "screen",
+                        "screen_portrait",
+                        format!("screen {} is portrait, which is rare on desktop", screen),
+                    ));
+                }
+            }
+            _ => out.push(issue(
+                Error,
+                "screen",
+                "screen_invalid",
+                format!(
+                    "screen {} is not a plausible WIDTHxHEIGHT resolution",
+                    screen
+                ),
+            )),
+        }
+    }
+    if let Some(dpr) = number(fp, "screenPixelRatio") {
+        if dpr <= 0.0 || dpr > 4.0 {
+            out.push(issue(
+                Error,
+                "screenPixelRatio",
+                "pixel_ratio_invalid",
+                format!("screenPixelRatio {} is out of range", dpr),
+            ));
+        } else if os == Some("macos") && dpr != 1.0 && dpr != 2.0 {
+            out.push(issue(
+                Warning,
+                "screenPixelRatio",
+                "pixel_ratio_unusual",
+                format!("screenPixelRatio {} is unusual on macOS", dpr),
+            ));
+        }
+    }
+    if let (Some(os), Some(fonts)) = (os, fp.get("fonts").and_then(|v| v.as_array())) {
+        if let Some(own) = fingerprint::os_fonts(os) {
+            let foreign: Vec<&str> = fonts
+                .iter()
+                .filter_map(|f| f.as_str())
+                .filter(|f| !own.contains(f))
+                .filter(|f| {
+                    KNOWN_OS
+                        .iter()
+                        .filter(|o| **o != os)
+                        .filter_map(|o| fingerprint::os_fonts(o))
+                        .any(|list| list.contains(f))
+                })
+                .collect();
+            if !foreign.is_empty() {
+                out.push(issue(
+                    Warning,
+                    "fonts",
+                    "fonts_os_mismatch",
+                    format!("fonts {} are not shipped with {}", foreign.join(", "), os),
+                ));
+            }
+        }
+    }
+    let languages = text(fp, "languages");
+    if languages.is_none() {
+        out.push(issue(
+            Warning,
+            "languages",
+            "languages_empty",
+            "languages is empty, the engine will fall back to the host locale".into(),
+        ));
+    }
+    if let Some(geo) = geo {
+        if let (Some(want), Some(tz)) = (geo.timezone.as_deref(), text(fp, "timezone")) {
+            if want != tz {
+                out.push(issue(
+                    Warning,
+                    "timezone",
+                    "timezone_geo_mismatch",
+                    format!(
+                        "timezone {} does not match the proxy exit timezone {}",
+                        tz, want
+                    ),
+                ));
+            }
+        }
+        let region = languages
+            .and_then(|l| l.split(',').next())
+            .and_then(|primary| primary.trim().split_once('-'))
+            .map(|(_, r)| r.to_uppercase());
+        if let (Some(country), Some(region)) = (geo.country_code.as_deref(), region) {
+            if !country.eq_ignore_ascii_case(&region) {
+                out.push(issue(
+                    Warning,
+                    "languages",
+                    "language_geo_mismatch",
+                    format!(
+                        "primary language region {} does not match the proxy country {}",
+                        region,
+                        country.to_uppercase()
+                    ),
+                ));
+            }
+        }
+    }
+    out
+}
+
+// 前端保存的是 JSON 字符串，命令同时接受字符串与对象
+fn parse_fp(fp: &Value) -> Value {
+    match fp {
+        Value::String(s) => serde_json::from_str(s).unwrap_or(Value::Null),
+        other => other.clone(),
+    }
+}
+
+pub(crate) fn has_errors(issues: &[FingerprintIssue]) -> bool {
+    issues.iter().any(|i| i.severity == Severity::Error)
+}
+
+// 检查 profile 中保存的指纹字符串；无法解析时不报告
+pub(crate) fn validate_saved(
+    raw: &str,
+    engine_version: Option<&str>,
+    geo: Option<&ProxyGeo>,
+) -> Vec<FingerprintIssue> {
+    let fp = parse_fp(&Value::String(raw.to_string()));
+    if fp.is_null() {
+        return Vec::new();
+    }
+    validate(&fp, geo, Some(fingerprint::engine_major(engine_version)))
+}
+
+// 启动前检查已保存的指纹：有错误时拒绝启动，警告只记录日志
+pub(crate) fn check_before_launch(
+    label: &str,
+    engine_version: Option<&str>,
+    geo: Option<&ProxyGeo>,
+) -> Result<(), String> {
+    let Some(raw) = profile_store::load(label).and_then(|c| c.fingerprint) else {
+        return Ok(());
+    };
+    let issues = validate_saved(&raw, engine_version, geo);
+    for i in &issues {
+        write_log(
+            if i.severity == Severity::Error {
+                "ERROR"
+            } else {
+                "WARN"
+            },
+            &format!("fingerprint label={} {}: {}", label, i.path, i.message),
+        );
+    }
+    if has_errors(&issues) {
+        let msgs: Vec<String> = issues
+            .iter()
+            .filter(|i| i.severity == Severity::Error)
+            .map(|i| format!("{}: {}", i.path, i.message))
+            .collect();
+        return Err(format!(
+            "fingerprint of {} is inconsistent: {}",
+            label,
+            msgs.join("; ")
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn fingerprint_validate(
+    fp: Value,
+    proxy_geo: Option<ProxyGeo>,
+    engine_version: Option<String>,
+) -> Vec<FingerprintIssue> {
+    let major = engine_version
+        .as_deref()
+        .map(|v| fingerprint::engine_major(Some(v)));
+    validate(&parse_fp(&fp), proxy_geo.as_ref(), major)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn codes(issues: &[FingerprintIssue]) -> Vec<&str> {
+        issues.iter().map(|i| i.code.as_str()).collect()
+    }
+
+    #[test]
+    fn test_generated_fingerprint_is_clean() {
+        for os in KNOWN_OS {
+            let fp = serde_json::to_value(fingerprint::generate(os, 9, 120).unwrap()).unwrap();
+            let issues = validate(&fp, None, Some(120));
+            assert!(issues.is_empty(), "{} {:?}", os, issues);
+        }
+    }
+
+    #[test]
+    fn test_contradictions_are_reported() {
+        let fp = json!({
+            "userAgent": "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/118.0.0.0 Safari/537.36",
+            "platform": "MacIntel",
+            "deviceMemory": "3",
+            "hardwareConcurrency": 8,
+            "screen": "1920x1080",
+            "webglVendor": "Google Inc. (Apple)",
+            "webglRenderer": "ANGLE (Apple, ANGLE Metal Renderer: Apple M1, Unspecified Version)",
+            "fonts": ["Arial", "Helvetica Neue"],
+            "languages": "zh-CN,zh",
+            "timezone": "Asia/Shanghai"
+        });
+        let geo = ProxyGeo {
+            country_code: Some("us".into()),
+            timezone: Some("America/New_York".into()),
+        };
+        let issues = validate(&fp, Some(&geo), Some(120));
+        let c = codes(&issues);
+        for want in [
+            "platform_mismatch",
+            "device_memory_invalid",
+            "webgl_os_mismatch",
+            "engine_version_mismatch",
+            "fonts_os_mismatch",
+            "timezone_geo_mismatch",
+            "language_geo_mismatch",
+        ] {
+            assert!(c.contains(&want), "missing {} in {:?}", want, c);
+        }
+        assert!(has_errors(&issues));
+        let mem = issues.iter().find(|i| i.path == "deviceMemory").unwrap();
+        assert_eq!(mem.severity, Severity::Error);
+        // 旧界面保存的 16/32 只警告，不阻止启动
+        let legacy = validate(&json!({ "deviceMemory": "16" }), None, None);
+        let mem = legacy.iter().find(|i| i.path == "deviceMemory").unwrap();
+        assert_eq!(mem.code, "device_memory_capped");
+        assert_eq!(mem.severity, Severity::Warning);
+        assert_eq!(
+            issues
+                .iter()
+                .find(|i| i.path == "timezone")
+                .unwrap()
+                .severity,
+            Severity::Warning
+        );
+    }
+}
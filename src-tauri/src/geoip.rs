@@ -0,0 +1,448 @@
+// 按代理出口 IP 对齐时区、语言与地理位置：经代理访问回显服务拿到出口 IP，再查离线 mmdb 库
+use crate::emulation::{self, Overrides};
+use crate::fingerprint_lint::ProxyGeo;
+use crate::profile_archive::sha256_hex;
+use crate::proxies::ResolvedProxy;
+use crate::{app_settings, data_local_base, relay, write_log};
+use chrono::Local;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+// 同一代理的解析结果缓存 6 小时
+const CACHE_TTL_SECS: i64 = 6 * 3600;
+const ECHO_TIMEOUT: Duration = Duration::from_secs(10);
+
+static CACHE: OnceCell<Mutex<HashMap<String, GeoInfo>>> = OnceCell::new();
+static APPLIED: OnceCell<Mutex<HashMap<String, GeoApplied>>> = OnceCell::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GeoInfo {
+    pub ip: String,
+    pub country_code: Option<String>,
+    pub timezone: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub accuracy_radius: Option<u16>,
+    // 按国家推导的界面语言，如 en-US
+    pub locale: Option<String>,
+    pub resolved_at: i64,
+}
+
+// 一次启动实际应用的内容
+#[derive(Debug, Clone, Serialize)]
+pub struct GeoApplied {
+    pub label: String,
+    pub geo: GeoInfo,
+    pub timezone: Option<String>,
+    pub lang: Option<String>,
+    // 地理位置需通过 CDP 设置，仅开启远程调试时生效
+    pub geolocation: bool,
+    pub applied_at: String,
+}
+
+impl GeoInfo {
+    pub(crate) fn proxy_geo(&self) -> ProxyGeo {
+        ProxyGeo {
+            country_code: self.country_code.clone(),
+            timezone: self.timezone.clone(),
+        }
+    }
+}
+
+// 国家代码到常用界面语言，未列出的国家只对齐时区
+const COUNTRY_LOCALES: &[(&str, &str)] = &[
+    ("AE", "ar-AE"),
+    ("AR", "es-AR"),
+    ("AT", "de-AT"),
+    ("AU", "en-AU"),
+    ("BE", "nl-BE"),
+    ("BR", "pt-BR"),
+    ("CA", "en-CA"),
+    ("CH", "de-CH"),
+    ("CL", "es-CL"),
+    ("CN", "zh-CN"),
+    ("CO", "es-CO"),
+    ("CZ", "cs-CZ"),
+    ("DE", "de-DE"),
+    ("DK", "da-DK"),
+    ("EG", "ar-EG"),
+    ("ES", "es-ES"),
+    ("FI", "fi-FI"),
+    ("FR", "fr-FR"),
+    ("GB", "en-GB"),
+    ("GR", "el-GR"),
+    ("HK", "zh-HK"),
+    ("HU", "hu-HU"),
+    ("ID", "id-ID"),
+    ("IE", "en-IE"),
+    ("IL", "he-IL"),
+    ("IN", "en-IN"),
+    ("IT", "it-IT"),
+    ("JP", "ja-JP"),
+    ("KR", "ko-KR"),
+    ("MX", "es-MX"),
+    ("MY", "ms-MY"),
+    ("NL", "nl-NL"),
+    ("NO", "nb-NO"),
+    ("NZ", "en-NZ"),
+    ("PH", "en-PH"),
+    ("PL", "pl-PL"),
+    ("PT", "pt-PT"),
+    ("RO", "ro-RO"),
+    ("RU", "ru-RU"),
+    ("SA", "ar-SA"),
+    ("SE", "sv-SE"),
+    ("SG", "en-SG"),
+    ("TH", "th-TH"),
+    ("TR", "tr-TR"),
+    ("TW", "zh-TW"),
+    ("UA", "uk-UA"),
+    ("US", "en-US"),
+    ("VN", "vi-VN"),
+    ("ZA", "en-ZA"),
+];
+
+fn cache() -> &'static Mutex<HashMap<String, GeoInfo>> {
+    CACHE.get_or_init(|| Mutex::new(load_cache()))
+}
+
+fn applied() -> &'static Mutex<HashMap<String, GeoApplied>> {
+    APPLIED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cache_path() -> PathBuf {
+    data_local_base().join("geoip_cache.json")
+}
+
+fn load_cache() -> HashMap<String, GeoInfo> {
+    fs::read_to_string(cache_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_cache(map: &HashMap<String, GeoInfo>) {
+    if let Ok(content) = serde_json::to_string_pretty(map) {
+        if let Err(e) = fs::write(cache_path(), content) {
+            write_log("WARN", &format!("write geoip cache failed: {}", e));
+        }
+    }
+}
+
+// 代理地址可能带账号密码，缓存键只保存哈希
+fn cache_key(proxy: &str) -> String {
+    sha256_hex(proxy.trim().as_bytes())
+}
+
+// 未配置时使用 data 目录下的默认位置
+pub(crate) fn db_path() -> PathBuf {
+    app_settings::current()
+        .geoip_db_path
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| data_local_base().join("geoip").join("GeoLite2-City.mmdb"))
+}
+
+pub(crate) fn locale_for_country(country: &str) -> Option<&'static str> {
+    let cc = country.to_uppercase();
+    COUNTRY_LOCALES
+        .iter()
+        .find(|(c, _)| *c == cc)
+        .map(|(_, l)| *l)
+}
+
+// 回显服务可能返回纯文本 IP 或 {"ip": "..."}
+pub(crate) fn parse_echo_body(body: &str) -> Option<IpAddr> {
+    let trimmed = body.trim();
+    if let Ok(ip) = trimmed.parse() {
+        return Some(ip);
+    }
+    let v: serde_json::Value = serde_json::from_str(trimmed).ok()?;
+    v.get("ip")?.as_str()?.trim().parse().ok()
+}
+
+pub(crate) fn resolve_exit_ip(proxy: &str, echo_url: &str) -> Result<IpAddr, String> {
+    let proxy = reqwest::Proxy::all(proxy.trim()).map_err(|e| format!("invalid proxy: {e}"))?;
+    let client = reqwest::blocking::Client::builder()
+        .proxy(proxy)
+        .timeout(ECHO_TIMEOUT)
+        .build()
+        .map_err(|e| format!("build http client failed: {e}"))?;
+    let body = client
+        .get(echo_url)
+        .send()
+        .and_then(|r| r.error_for_status())
+        .and_then(|r| r.text())
+        .map_err(|e| format!("resolve exit ip failed: {e}"))?;
+    parse_echo_body(&body).ok_or_else(|| format!("unexpected ip echo response: {}", body.trim()))
+}
+
+pub(crate) fn lookup(ip: IpAddr) -> Result<GeoInfo, String> {
+    let path = db_path();
+    let reader = maxminddb::Reader::open_readfile(&path)
+        .map_err(|e| format!("open geoip db {} failed: {e}", path.display()))?;
+    let city: maxminddb::geoip2::City = reader
+        .lookup(ip)
+        .map_err(|e| format!("geoip lookup {} failed: {e}", ip))?;
+    let country_code = city.country.and_then(|c| c.iso_code).map(|c| c.to_string());
+    let location = city.location;
+    Ok(GeoInfo {
+        ip: ip.to_string(),
+        locale: country_code
+            .as_deref()
+            .and_then(locale_for_country)
+            .map(str::to_string),
+        country_code,
+        timezone: location
+            .as_ref()
+            .and_then(|l| l.time_zone)
+            .map(|t| t.to_string()),
+        latitude: location.as_ref().and_then(|l| l.latitude),
+        longitude: location.as_ref().and_then(|l| l.longitude),
+        accuracy_radius: location.as_ref().and_then(|l| l.accuracy_radius),
+        resolved_at: Local::now().timestamp(),
+    })
+}
+
+pub(crate) fn resolve(proxy: &str, force: bool) -> Result<GeoInfo, String> {
+    resolve_keyed(proxy, force, |echo| resolve_exit_ip(proxy, echo))
+}
+
+// 缓存键与取出口 IP 的方式分开，多跳代理按整条链路缓存
+fn resolve_keyed(
+    key_source: &str,
+    force: bool,
+    fetch: impl FnOnce(&str) -> Result<IpAddr, String>,
+) -> Result<GeoInfo, String> {
+    let key = cache_key(key_source);
+    let now = Local::now().timestamp();
+    if !force {
+        if let Some(hit) = cache().lock().ok().and_then(|m| m.get(&key).cloned()) {
+            if now - hit.resolved_at < CACHE_TTL_SECS {
+                return Ok(hit);
+            }
+        }
+    }
+    // 没有数据库时查到出口 IP 也无从定位，不必经代理多跑一趟
+    let db = db_path();
+    if !db.is_file() {
+        return Err(format!("geoip db {} not found", db.display()));
+    }
+    let ip = fetch(&app_settings::current().ip_echo_url)?;
+    let info = lookup(ip)?;
+    if let Ok(mut m) = cache().lock() {
+        m.insert(key, info.clone());
+        save_cache(&m);
+    }
+    Ok(info)
+}
+
+// 启动前调用：关闭对齐或解析失败时返回 None，不阻止启动
+pub(crate) fn resolve_for_launch(label: &str, proxy: Option<&ResolvedProxy>) -> Option<GeoInfo> {
+    let proxy = proxy.filter(|p| !p.url.trim().is_empty())?;
+    if !app_settings::current().geo_align {
+        return None;
+    }
+    // 应用不附带数据库，未配置时静默跳过
+    if !db_path().is_file() {
+        write_log(
+            "INFO",
+            &format!("geo align skipped label={}: no geoip db configured", label),
+        );
+        return None;
+    }
+    let result = if proxy.chain.is_empty() {
+        resolve(&proxy.url, false)
+    } else {
+        // 多跳代理经临时中转查询出口 IP
+        let key = format!("{} > {}", proxy.chain.join(" > "), proxy.url);
+        resolve_keyed(&key, false, |echo| {
+            relay::probe(&proxy.chain, &proxy.url, |u| resolve_exit_ip(u, echo))?
+        })
+    };
+    match result {
+        Ok(info) => Some(info),
+        Err(e) => {
+            write_log(
+                "WARN",
+                &format!("geo align skipped label={} err={}", label, e),
+            );
+            None
+        }
+    }
+}
+
+pub(crate) fn launch_args(geo: &GeoInfo) -> Vec<String> {
+    let Some(locale) = geo.locale.as_deref() else {
+        return Vec::new();
+    };
+    let lang = locale.split('-').next().unwrap_or(locale);
+    vec![
+        format!("--lang={}", locale),
+        format!("--accept-lang={},{}", locale, lang),
+    ]
+}
+
+// Chromium 在 macOS/Linux 上读取 TZ；Windows 上依赖 CDP 的时区覆盖
+pub(crate) fn launch_env(geo: &GeoInfo) -> Vec<(String, String)> {
+    geo.timezone
+        .iter()
+        .map(|tz| ("TZ".to_string(), tz.clone()))
+        .collect()
+}
+
+// 时区与地理位置覆盖，经常驻连接作用于所有页面
+fn cdp_overrides(geo: &GeoInfo) -> Overrides {
+    let mut o = Overrides::default();
+    if let (Some(lat), Some(lon)) = (geo.latitude, geo.longitude) {
+        o.browser.push((
+            "Browser.grantPermissions".into(),
+            json!({ "permissions": ["geolocation"] }),
+        ));
+        o.session.push((
+            "Emulation.setGeolocationOverride".into(),
+            json!({
+                "latitude": lat,
+                "longitude": lon,
+                "accuracy": geo.accuracy_radius.unwrap_or(50) as f64 * 1000.0,
+            }),
+        ));
+    }
+    if let Some(tz) = geo.timezone.as_deref() {
+        o.session.push((
+            "Emulation.setTimezoneOverride".into(),
+            json!({ "timezoneId": tz }),
+        ));
+    }
+    o
+}
+
+fn store_applied(app: &AppHandle, report: GeoApplied) {
+    write_log(
+        "INFO",
+        &format!(
+            "geo applied label={} ip={} country={:?} tz={:?} lang={:?} geolocation={}",
+            report.label,
+            report.geo.ip,
+            report.geo.country_code,
+            report.timezone,
+            report.lang,
+            report.geolocation
+        ),
+    );
+    let _ = app.emit("browser-geo-applied", &report);
+    if let Ok(mut m) = applied().lock() {
+        m.insert(report.label.clone(), report);
+    }
+}
+
+// 启动成功后记录并上报实际应用的内容；开启远程调试时在后台补上地理位置
+pub(crate) fn report_applied(app: &AppHandle, label: &str, geo: GeoInfo, remote_debugging: bool) {
+    let report = GeoApplied {
+        label: label.to_string(),
+        // Windows 上不读取 TZ，只有 CDP 覆盖成功后才算生效
+        timezone: geo.timezone.clone().filter(|_| !cfg!(windows)),
+        lang: geo.locale.clone(),
+        geolocation: false,
+        applied_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        geo,
+    };
+    store_applied(app, report.clone());
+    if !remote_debugging {
+        return;
+    }
+    let app = app.clone();
+    let label = label.to_string();
+    std::thread::spawn(
+        move || match emulation::add(&label, cdp_overrides(&report.geo)) {
+            Ok(()) => store_applied(
+                &app,
+                GeoApplied {
+                    geolocation: report.geo.latitude.is_some(),
+                    timezone: report.geo.timezone.clone(),
+                    ..report
+                },
+            ),
+            Err(e) => write_log(
+                "WARN",
+                &format!("geo cdp override failed label={} err={}", label, e),
+            ),
+        },
+    );
+}
+
+#[tauri::command]
+pub(crate) fn proxy_geo_lookup(proxy: &str, force: Option<bool>) -> Result<GeoInfo, String> {
+    resolve(proxy, force.unwrap_or(false))
+}
+
+#[tauri::command]
+pub(crate) fn browser_geo_applied(label: &str) -> Option<GeoApplied> {
+    applied().lock().ok()?.get(label).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> GeoInfo {
+        GeoInfo {
+            ip: "203.0.113.7".into(),
+            country_code: Some("DE".into()),
+            timezone: Some("Europe/Berlin".into()),
+            latitude: Some(52.5),
+            longitude: Some(13.4),
+            accuracy_radius: Some(20),
+            locale: locale_for_country("de").map(str::to_string),
+            resolved_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_echo_body() {
+        assert_eq!(
+            parse_echo_body(" 203.0.113.7\n"),
+            Some("203.0.113.7".parse().unwrap())
+        );
+        assert_eq!(
+            parse_echo_body(r#"{"ip":"2001:db8::1"}"#),
+            Some("2001:db8::1".parse().unwrap())
+        );
+        assert_eq!(parse_echo_body("<html>blocked</html>"), None);
+    }
+
+    #[test]
+    fn test_launch_overrides_follow_geo() {
+        let geo = sample();
+        assert_eq!(
+            launch_args(&geo),
+            vec!["--lang=de-DE", "--accept-lang=de-DE,de"]
+        );
+        assert_eq!(
+            launch_env(&geo),
+            vec![("TZ".to_string(), "Europe/Berlin".to_string())]
+        );
+        assert_eq!(geo.proxy_geo().country_code.as_deref(), Some("DE"));
+        let unknown = GeoInfo {
+            locale: locale_for_country("AQ").map(str::to_string),
+            timezone: None,
+            ..sample()
+        };
+        assert!(launch_args(&unknown).is_empty());
+        assert!(launch_env(&unknown).is_empty());
+        assert_ne!(
+            cache_key("socks5://u:p@1.2.3.4:1080"),
+            cache_key("socks5://u:q@1.2.3.4:1080")
+        );
+        assert!(!cache_key("socks5://u:p@1.2.3.4:1080").contains("u:p"));
+    }
+}
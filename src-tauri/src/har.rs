@@ -0,0 +1,529 @@
+// 经远程调试协议录制运行中 profile 的网络请求，停止时写出 HAR 1.2 到 profile 目录下的 har/
+use crate::cdp::{Attached, CdpClient};
+use crate::{profile_dir_path, write_log};
+use chrono::{DateTime, Local, SecondsFormat};
+use once_cell::sync::OnceCell;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// 单次录制的条目上限，超出后不再记录新请求
+const MAX_ENTRIES: usize = 20_000;
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+// 支持 Network 域的目标；页面与 iframe 之下还会出现专用 worker
+const NETWORK_TARGETS: &[&str] = &[
+    "page",
+    "iframe",
+    "worker",
+    "service_worker",
+    "shared_worker",
+];
+
+static RECORDERS: OnceCell<Mutex<HashMap<String, Recorder>>> = OnceCell::new();
+
+struct Recorder {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Vec<Exchange>>,
+}
+
+// 一次请求及其响应，时间戳为 CDP 的单调时钟（秒）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Exchange {
+    // 请求开始时的墙钟时间（秒）
+    pub wall_time: f64,
+    pub request_ts: f64,
+    pub method: String,
+    pub url: String,
+    pub request_headers: Vec<(String, String)>,
+    pub post_data: Option<String>,
+    pub resource_type: Option<String>,
+    pub status: u16,
+    pub status_text: String,
+    pub protocol: Option<String>,
+    pub response_headers: Vec<(String, String)>,
+    pub mime_type: String,
+    pub remote_ip: Option<String>,
+    pub response_ts: Option<f64>,
+    pub finished_ts: Option<f64>,
+    pub encoded_size: Option<i64>,
+    pub error: Option<String>,
+}
+
+fn recorders() -> &'static Mutex<HashMap<String, Recorder>> {
+    RECORDERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// CDP 的请求头为对象
+fn headers(v: &Value) -> Vec<(String, String)> {
+    v.as_object()
+        .map(|m| {
+            m.iter()
+                .map(|(k, v)| (k.clone(), v.as_str().unwrap_or_default().to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn apply_response(ex: &mut Exchange, r: &Value, ts: f64) {
+    ex.status = r["status"].as_u64().unwrap_or(0) as u16;
+    ex.status_text = r["statusText"].as_str().unwrap_or_default().to_string();
+    ex.protocol = r["protocol"].as_str().map(str::to_string);
+    ex.response_headers = headers(&r["headers"]);
+    ex.mime_type = r["mimeType"].as_str().unwrap_or_default().to_string();
+    ex.remote_ip = r["remoteIPAddress"].as_str().map(str::to_string);
+    ex.response_ts = Some(ts);
+}
+
+// 按 (会话, requestId) 归并 Network 事件
+#[derive(Default)]
+struct Book {
+    open: HashMap<String, Exchange>,
+    done: Vec<Exchange>,
+    truncated: bool,
+}
+
+impl Book {
+    fn len(&self) -> usize {
+        self.open.len() + self.done.len()
+    }
+
+    fn handle(&mut self, session: &str, method: &str, p: &Value) {
+        let key = format!(
+            "{}/{}",
+            session,
+            p["requestId"].as_str().unwrap_or_default()
+        );
+        let ts = p["timestamp"].as_f64().unwrap_or(0.0);
+        match method {
+            "Network.requestWillBeSent" => {
+                // 重定向沿用同一 requestId，先结束上一跳
+                if let Some(mut prev) = self.open.remove(&key) {
+                    if p["redirectResponse"].is_object() {
+                        apply_response(&mut prev, &p["redirectResponse"], ts);
+                    }
+                    prev.finished_ts = Some(ts);
+                    self.done.push(prev);
+                }
+                if self.len() >= MAX_ENTRIES {
+                    self.truncated = true;
+                    return;
+                }
+                let r = &p["request"];
+                self.open.insert(
+                    key,
+                    Exchange {
+                        wall_time: p["wallTime"].as_f64().unwrap_or(0.0),
+                        request_ts: ts,
+                        method: r["method"].as_str().unwrap_or("GET").to_string(),
+                        url: r["url"].as_str().unwrap_or_default().to_string(),
+                        request_headers: headers(&r["headers"]),
+                        post_data: r["postData"].as_str().map(str::to_string),
+                        resource_type: p["type"].as_str().map(str::to_string),
+                        ..Default::default()
+                    },
+                );
+            }
+            "Network.responseReceived" => {
+                if let Some(ex) = self.open.get_mut(&key) {
+                    apply_response(ex, &p["response"], ts);
+                }
+            }
+            "Network.loadingFinished" => {
+                if let Some(mut ex) = self.open.remove(&key) {
+                    ex.finished_ts = Some(ts);
+                    ex.encoded_size = p["encodedDataLength"].as_f64().map(|n| n as i64);
+                    self.done.push(ex);
+                }
+            }
+            "Network.loadingFailed" => {
+                if let Some(mut ex) = self.open.remove(&key) {
+                    ex.finished_ts = Some(ts);
+                    ex.error = p["errorText"].as_str().map(str::to_string);
+                    self.done.push(ex);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // 停止时仍未完成的请求也写入
+    fn finish(mut self) -> Vec<Exchange> {
+        self.done.extend(self.open.into_values());
+        self.done
+            .sort_by(|a, b| a.request_ts.total_cmp(&b.request_ts));
+        self.done
+    }
+}
+
+// 新目标暂停在首个请求之前：启用 Network 并继续自动附加其子目标后再放行，失败时也要放行
+fn attach(client: &mut CdpClient, attached: &Attached) -> Result<(), String> {
+    let sid = attached.session_id.as_str();
+    let kind = attached.target_type.as_str();
+    let mut res = Ok(());
+    if NETWORK_TARGETS.contains(&kind) {
+        res = client
+            .call_in(Some(sid), "Network.enable", json!({}))
+            .map(|_| ());
+        if res.is_ok() && matches!(kind, "page" | "iframe") {
+            res = client.auto_attach(Some(sid));
+        }
+    }
+    res.and(client.resume(attached))
+}
+
+// 录制已打开与之后新开的页面、iframe 与 worker；连接断开（如浏览器退出）时结束并保留已录制的内容
+fn record(mut client: CdpClient, label: &str, stop: &AtomicBool) -> Vec<Exchange> {
+    let mut book = Book::default();
+    while !stop.load(Ordering::SeqCst) {
+        let ev = match client.next_event(POLL_INTERVAL) {
+            Ok(Some(ev)) => ev,
+            Ok(None) => continue,
+            Err(e) => {
+                write_log(
+                    "WARN",
+                    &format!("har recording ended label={} err={}", label, e),
+                );
+                break;
+            }
+        };
+        let method = ev["method"].as_str().unwrap_or_default();
+        let params = &ev["params"];
+        if let Some(attached) = Attached::from_event(&ev) {
+            if let Err(e) = attach(&mut client, &attached) {
+                write_log(
+                    "WARN",
+                    &format!(
+                        "har attach failed label={} type={} err={}",
+                        label, attached.target_type, e
+                    ),
+                );
+            }
+        } else if method.starts_with("Network.") {
+            let session = ev["sessionId"].as_str().unwrap_or_default();
+            book.handle(session, method, params);
+        }
+    }
+    if book.truncated {
+        write_log(
+            "WARN",
+            &format!(
+                "har recording of {} truncated at {} entries",
+                label, MAX_ENTRIES
+            ),
+        );
+    }
+    book.finish()
+}
+
+fn start_recording(label: &str, mut client: CdpClient) -> Result<(), String> {
+    let mut m = recorders()
+        .lock()
+        .map_err(|_| "har recorder lock poisoned".to_string())?;
+    if m.contains_key(label) {
+        return Err(format!("har recording of {} is already running", label));
+    }
+    // 已打开的目标随即收到 attachedToTarget，由录制线程处理
+    client.auto_attach(None)?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let flag = stop.clone();
+    let lbl = label.to_string();
+    let handle = thread::spawn(move || record(client, &lbl, &flag));
+    m.insert(label.to_string(), Recorder { stop, handle });
+    Ok(())
+}
+
+fn stop_recording(label: &str, dir: &Path) -> Result<PathBuf, String> {
+    let recorder = recorders()
+        .lock()
+        .map_err(|_| "har recorder lock poisoned".to_string())?
+        .remove(label)
+        .ok_or_else(|| format!("no har recording of {} is running", label))?;
+    recorder.stop.store(true, Ordering::SeqCst);
+    let entries = recorder
+        .handle
+        .join()
+        .map_err(|_| "har recorder panicked".to_string())?;
+    fs::create_dir_all(dir).map_err(|e| format!("create har dir failed: {e}"))?;
+    let path = dir.join(format!("{}.har", Local::now().format("%Y%m%d-%H%M%S")));
+    let content = serde_json::to_string_pretty(&build_har(&entries))
+        .map_err(|e| format!("serialize har failed: {e}"))?;
+    fs::write(&path, content).map_err(|e| format!("write har failed: {e}"))?;
+    write_log(
+        "INFO",
+        &format!(
+            "har saved label={} entries={} path={}",
+            label,
+            entries.len(),
+            path.display()
+        ),
+    );
+    Ok(path)
+}
+
+fn name_values(pairs: &[(String, String)]) -> Value {
+    pairs
+        .iter()
+        .map(|(k, v)| json!({ "name": k, "value": v }))
+        .collect()
+}
+
+fn header<'a>(pairs: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    pairs
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+// 毫秒；缺少时间点时按 HAR 约定记为 -1
+fn span(from: f64, to: Option<f64>) -> f64 {
+    to.map_or(-1.0, |t| ((t - from) * 1000.0).max(0.0))
+}
+
+fn har_entry(ex: &Exchange) -> Value {
+    let started = DateTime::from_timestamp_millis((ex.wall_time * 1000.0) as i64)
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Millis, true);
+    let wait = span(ex.request_ts, ex.response_ts);
+    let receive = match (ex.response_ts, ex.finished_ts) {
+        (Some(r), Some(f)) => ((f - r) * 1000.0).max(0.0),
+        _ => -1.0,
+    };
+    let query: Vec<(String, String)> = url::Url::parse(&ex.url)
+        .map(|u| u.query_pairs().into_owned().collect())
+        .unwrap_or_default();
+    let http_version = ex
+        .protocol
+        .as_deref()
+        .map(str::to_ascii_uppercase)
+        .unwrap_or_else(|| "HTTP/1.1".into());
+    let mut request = json!({
+        "method": ex.method,
+        "url": ex.url,
+        "httpVersion": http_version,
+        "cookies": [],
+        "headers": name_values(&ex.request_headers),
+        "queryString": name_values(&query),
+        "headersSize": -1,
+        "bodySize": ex.post_data.as_ref().map_or(0, |d| d.len() as i64),
+    });
+    if let Some(data) = &ex.post_data {
+        request["postData"] = json!({
+            "mimeType": header(&ex.request_headers, "content-type").unwrap_or_default(),
+            "text": data,
+        });
+    }
+    let mut entry = json!({
+        "startedDateTime": started,
+        "time": span(ex.request_ts, ex.finished_ts.or(ex.response_ts)),
+        "request": request,
+        "response": {
+            "status": ex.status,
+            "statusText": ex.status_text,
+            "httpVersion": http_version,
+            "cookies": [],
+            "headers": name_values(&ex.response_headers),
+            "content": {
+                "size": ex.encoded_size.unwrap_or(-1),
+                "mimeType": ex.mime_type,
+            },
+            "redirectURL": header(&ex.response_headers, "location").unwrap_or_default(),
+            "headersSize": -1,
+            "bodySize": ex.encoded_size.unwrap_or(-1),
+        },
+        "cache": {},
+        "timings": { "send": 0, "wait": wait, "receive": receive },
+    });
+    let extra: &mut Map<String, Value> = entry.as_object_mut().expect("entry is an object");
+    if let Some(ip) = &ex.remote_ip {
+        extra.insert("serverIPAddress".into(), json!(ip));
+    }
+    if let Some(t) = &ex.resource_type {
+        extra.insert("_resourceType".into(), json!(t));
+    }
+    if let Some(e) = &ex.error {
+        extra.insert("_error".into(), json!(e));
+    }
+    entry
+}
+
+pub(crate) fn build_har(entries: &[Exchange]) -> Value {
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": "Libre Browser", "version": env!("CARGO_PKG_VERSION") },
+            "pages": [],
+            "entries": entries.iter().map(har_entry).collect::<Vec<_>>(),
+        }
+    })
+}
+
+// 需要以远程调试方式启动
+#[tauri::command]
+pub(crate) fn browser_har_start(label: &str) -> Result<(), String> {
+    let client = CdpClient::for_label(label)?;
+    start_recording(label, client)?;
+    write_log("INFO", &format!("har recording started label={}", label));
+    Ok(())
+}
+
+// 返回写出的 HAR 文件路径
+#[tauri::command]
+pub(crate) fn browser_har_stop(label: &str) -> Result<String, String> {
+    stop_recording(label, &profile_dir_path(label).join("har"))
+        .map(|p| p.to_string_lossy().to_string())
+}
+
+// 关闭浏览器前保存仍在进行的录制，避免残留的录制阻塞下一次开始
+pub(crate) fn save_before_close(label: &str) {
+    let running = recorders()
+        .lock()
+        .map(|m| m.contains_key(label))
+        .unwrap_or(false);
+    if running {
+        if let Err(e) = browser_har_stop(label) {
+            write_log(
+                "WARN",
+                &format!("har save on close failed label={} err={}", label, e),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc;
+    use tungstenite::Message;
+
+    // 本地 HTTP 服务：返回固定页面
+    fn http_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for mut s in listener.incoming().flatten() {
+                let mut buf = [0u8; 2048];
+                let _ = s.read(&mut buf);
+                let _ = s.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+                );
+            }
+        });
+        port
+    }
+
+    // CDP 回放：不驱动真实引擎，按脚本模拟 DevTools 端点。自动附加后给出一个暂停的页面，
+    // 页面再自动附加出一个暂停的 worker；页面启用 Network 后真实请求本地服务，并按结果发出事件
+    fn replay_devtools(http_port: u16, seen: mpsc::Sender<(String, String)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut ws = tungstenite::accept(stream).unwrap();
+            let attached = |sid: &str, kind: &str| {
+                json!({ "method": "Target.attachedToTarget", "params": {
+                    "sessionId": sid, "waitingForDebugger": true,
+                    "targetInfo": { "targetId": sid, "type": kind } } })
+            };
+            while let Ok(Message::Text(text)) = ws.read() {
+                let msg: Value = serde_json::from_str(&text).unwrap();
+                let method = msg["method"].as_str().unwrap_or_default().to_string();
+                let sid = msg["sessionId"].as_str().unwrap_or_default().to_string();
+                let reply = json!({ "id": msg["id"], "result": {} });
+                ws.send(Message::Text(reply.to_string())).unwrap();
+                let _ = seen.send((sid.clone(), method.clone()));
+                match (sid.as_str(), method.as_str()) {
+                    ("", "Target.setAutoAttach") => {
+                        ws.send(Message::Text(attached("S1", "page").to_string()))
+                            .unwrap();
+                    }
+                    ("S1", "Target.setAutoAttach") => {
+                        ws.send(Message::Text(attached("W1", "worker").to_string()))
+                            .unwrap();
+                    }
+                    ("S1", "Network.enable") => {}
+                    _ => continue,
+                }
+                if method != "Network.enable" {
+                    continue;
+                }
+                let url = format!("http://127.0.0.1:{}/page?q=1", http_port);
+                let mut s = TcpStream::connect(("127.0.0.1", http_port)).unwrap();
+                write!(s, "GET /page?q=1 HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n").unwrap();
+                let mut raw = String::new();
+                s.read_to_string(&mut raw).unwrap();
+                let (head, _) = raw.split_once("\r\n\r\n").unwrap();
+                let status: u64 = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+                let events = [
+                    json!({ "method": "Network.requestWillBeSent", "sessionId": "S1", "params": {
+                        "requestId": "R1", "timestamp": 100.0, "wallTime": 1_767_225_600.0, "type": "Document",
+                        "request": { "method": "GET", "url": url, "headers": { "Accept": "text/html" } } } }),
+                    json!({ "method": "Network.responseReceived", "sessionId": "S1", "params": {
+                        "requestId": "R1", "timestamp": 100.05,
+                        "response": { "status": status, "statusText": "OK", "protocol": "http/1.1",
+                            "mimeType": "text/html", "remoteIPAddress": "127.0.0.1",
+                            "headers": { "Content-Type": "text/html" } } } }),
+                    json!({ "method": "Network.loadingFinished", "sessionId": "S1", "params": {
+                        "requestId": "R1", "timestamp": 100.08, "encodedDataLength": raw.len() } }),
+                ];
+                for ev in events {
+                    ws.send(Message::Text(ev.to_string())).unwrap();
+                }
+            }
+        });
+        format!("ws://127.0.0.1:{}/devtools/browser/replay", port)
+    }
+
+    #[test]
+    fn test_har_capture_cdp_replay() {
+        let http_port = http_server();
+        let (tx, seen) = mpsc::channel();
+        let ws = replay_devtools(http_port, tx);
+        start_recording("HAR-T1", CdpClient::connect(&ws).unwrap()).unwrap();
+        // 页面与 worker 都在启用 Network 之后才放行
+        let mut log = Vec::new();
+        while log
+            .iter()
+            .filter(|(_, m)| m == "Runtime.runIfWaitingForDebugger")
+            .count()
+            < 2
+        {
+            log.push(seen.recv_timeout(Duration::from_secs(5)).unwrap());
+        }
+        thread::sleep(Duration::from_millis(300));
+        let at = |sid: &str, m: &str| log.iter().position(|(s, x)| s == sid && x == m).unwrap();
+        for sid in ["S1", "W1"] {
+            assert!(at(sid, "Network.enable") < at(sid, "Runtime.runIfWaitingForDebugger"));
+        }
+        assert!(at("S1", "Target.setAutoAttach") < at("S1", "Runtime.runIfWaitingForDebugger"));
+
+        let dir = std::env::temp_dir().join(format!("libre-har-{}", std::process::id()));
+        let path = stop_recording("HAR-T1", &dir).unwrap();
+        let har: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(har["log"]["version"], "1.2");
+        let entries = har["log"]["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        let e = &entries[0];
+        assert_eq!(
+            e["request"]["url"],
+            format!("http://127.0.0.1:{}/page?q=1", http_port)
+        );
+        assert_eq!(
+            e["request"]["queryString"][0],
+            json!({ "name": "q", "value": "1" })
+        );
+        assert_eq!(e["response"]["status"], 200);
+        assert_eq!(e["response"]["httpVersion"], "HTTP/1.1");
+        assert_eq!(e["startedDateTime"], "2026-01-01T00:00:00.000Z");
+        assert_eq!(e["timings"]["wait"].as_f64().unwrap().round(), 50.0);
+        assert_eq!(e["time"].as_f64().unwrap().round(), 80.0);
+        assert!(stop_recording("HAR-T1", &dir).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+// 启动队列：限制同时运行与同时启动的浏览器数量，按请求先后排队
+use crate::{app_settings, browser_running, sessions, write_log};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+static QUEUE: OnceCell<(Mutex<QueueState>, Condvar)> = OnceCell::new();
+
+#[derive(Default)]
+struct QueueState {
+    waiting: VecDeque<Ticket>,
+    launching: usize,
+    // 已拿到名额、尚未启动完成的 label
+    active: HashSet<String>,
+    next_id: u64,
+}
+
+struct Ticket {
+    id: u64,
+    label: String,
+    cancelled: bool,
+}
+
+// 推送给前端的排队位置，position 为 0 表示已开始启动
+#[derive(Debug, Serialize, Clone)]
+struct QueueEvent {
+    label: String,
+    position: usize,
+    queued: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct QueueEntry {
+    pub label: String,
+    pub position: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct QueueStatus {
+    pub running: usize,
+    pub launching: usize,
+    pub waiting: Vec<QueueEntry>,
+    pub max_running_browsers: u32,
+    pub max_concurrent_launches: u32,
+}
+
+fn queue() -> &'static (Mutex<QueueState>, Condvar) {
+    QUEUE.get_or_init(|| (Mutex::new(QueueState::default()), Condvar::new()))
+}
+
+// 持有期间占用一个启动名额，drop 时释放并唤醒排队者
+pub(crate) struct LaunchPermit {
+    label: String,
+}
+
+impl Drop for LaunchPermit {
+    fn drop(&mut self) {
+        let (lock, cvar) = queue();
+        if let Ok(mut st) = lock.lock() {
+            st.launching = st.launching.saturating_sub(1);
+            st.active.remove(&self.label);
+        }
+        cvar.notify_all();
+    }
+}
+
+fn can_start(launching: usize, running: usize, max_running: u32, max_launches: u32) -> bool {
+    if max_launches > 0 && launching >= max_launches as usize {
+        return false;
+    }
+    // 正在启动的也会占用运行名额
+    if max_running > 0 && running + launching >= max_running as usize {
+        return false;
+    }
+    true
+}
+
+// 自动重启没有前端请求方，不推送
+fn emit_position(app: Option<&AppHandle>, label: &str, position: usize, queued: usize) {
+    let Some(app) = app else {
+        return;
+    };
+    let payload = QueueEvent {
+        label: label.to_string(),
+        position,
+        queued,
+    };
+    if let Err(e) = app.emit("browser-launch-queue", payload) {
+        write_log("WARN", &format!("emit browser-launch-queue failed: {}", e));
+    }
+}
+
+// 阻塞直到轮到该 label 且有空闲名额；限制在等待期间修改也会生效
+pub(crate) fn acquire(app: Option<&AppHandle>, label: &str) -> Result<LaunchPermit, String> {
+    let (lock, cvar) = queue();
+    let mut st = lock
+        .lock()
+        .map_err(|e| format!("launch queue poisoned: {e}"))?;
+    if st.waiting.iter().any(|t| t.label == label) {
+        return Err(format!("{} is already queued", label));
+    }
+    // 同一 user-data-dir 不能被两个浏览器同时使用
+    if st.active.contains(label) {
+        return Err(format!("{} is already launching", label));
+    }
+    if let Some(pid) = browser_running(label) {
+        return Err(format!("{} is already running (pid {})", label, pid));
+    }
+    let id = st.next_id;
+    st.next_id += 1;
+    st.waiting.push_back(Ticket {
+        id,
+        label: label.to_string(),
+        cancelled: false,
+    });
+    let mut last_position = usize::MAX;
+    loop {
+        let Some(position) = st.waiting.iter().position(|t| t.id == id) else {
+            return Err("launch request lost".into());
+        };
+        if st.waiting[position].cancelled {
+            st.waiting.remove(position);
+            cvar.notify_all();
+            write_log("INFO", &format!("launch queue cancelled label={}", label));
+            return Err("launch cancelled".into());
+        }
+        let settings = app_settings::current();
+        if position == 0
+            && can_start(
+                st.launching,
+                sessions::running_count(),
+                settings.max_running_browsers,
+                settings.max_concurrent_launches,
+            )
+        {
+            st.waiting.pop_front();
+            st.launching += 1;
+            st.active.insert(label.to_string());
+            let queued = st.waiting.len();
+            drop(st);
+            cvar.notify_all();
+            if last_position != usize::MAX && last_position > 0 {
+                write_log("INFO", &format!("launch queue released label={}", label));
+            }
+            emit_position(app, label, 0, queued);
+            return Ok(LaunchPermit {
+                label: label.to_string(),
+            });
+        }
+        // 位置从 1 开始计数
+        let shown = position + 1;
+        if shown != last_position {
+            if last_position == usize::MAX {
+                write_log(
+                    "INFO",
+                    &format!("launch queue wait label={} position={}", label, shown),
+                );
+            }
+            last_position = shown;
+            emit_position(app, label, shown, st.waiting.len());
+        }
+        // 运行中的浏览器退出不会主动通知队列，定时复查
+        st = cvar
+            .wait_timeout(st, Duration::from_millis(500))
+            .map_err(|e| format!("launch queue poisoned: {e}"))?
+            .0;
+    }
+}
+
+#[tauri::command]
+pub(crate) fn browser_launch_cancel(label: &str) -> bool {
+    let (lock, cvar) = queue();
+    let mut found = false;
+    if let Ok(mut st) = lock.lock() {
+        for t in st.waiting.iter_mut().filter(|t| t.label == label) {
+            t.cancelled = true;
+            found = true;
+        }
+    }
+    cvar.notify_all();
+    found
+}
+
+#[tauri::command]
+pub(crate) fn browser_launch_queue() -> QueueStatus {
+    let settings = app_settings::current();
+    let (lock, _) = queue();
+    let (launching, waiting) = lock
+        .lock()
+        .map(|st| {
+            let waiting = st
+                .waiting
+                .iter()
+                .enumerate()
+                .map(|(i, t)| QueueEntry {
+                    label: t.label.clone(),
+                    position: i + 1,
+                })
+                .collect();
+            (st.launching, waiting)
+        })
+        .unwrap_or_default();
+    QueueStatus {
+        running: sessions::running_count(),
+        launching,
+        waiting,
+        max_running_browsers: settings.max_running_browsers,
+        max_concurrent_launches: settings.max_concurrent_launches,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_can_start_respects_both_limits() {
+        // 0 表示不限
+        assert!(can_start(10, 100, 0, 0));
+        assert!(!can_start(2, 0, 0, 2));
+        assert!(can_start(1, 0, 0, 2));
+        // 运行 3 + 启动中 1 达到上限 4
+        assert!(!can_start(1, 3, 4, 0));
+        assert!(can_start(0, 3, 4, 0));
+    }
+}
@@ -10,7 +10,7 @@ use std::{
     io::{Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
 };
-use tauri::{AppHandle, Manager, RunEvent, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+use tauri::{AppHandle, Emitter, Manager, RunEvent, WebviewUrl, WebviewWindowBuilder, WindowEvent};
 
 use image::{ImageBuffer, Rgba};
 use sha2::{Digest, Sha256};
@@ -20,6 +20,36 @@ use std::collections::{HashMap, HashSet};
 use std::process::{Child, Command, Stdio};
 use std::{thread, time::Duration};
 
+mod app_settings;
+mod cdp;
+mod cookies;
+mod dns;
+mod emulation;
+mod fingerprint;
+mod fingerprint_lint;
+mod geoip;
+mod har;
+mod launch_queue;
+mod policy;
+mod profile_archive;
+mod profile_clone;
+mod profile_store;
+mod proxies;
+mod proxy_check;
+mod proxy_import;
+mod proxy_session;
+mod relay;
+mod request_rules;
+mod run_history;
+mod sessions;
+mod snapshots;
+mod stats;
+mod supervisor;
+mod throttle;
+mod traffic;
+mod vault;
+mod webrtc;
+
 static LOG_FILE: OnceCell<std::sync::Mutex<File>> = OnceCell::new();
 static PROC_MAP: OnceCell<std::sync::Mutex<HashMap<String, Child>>> = OnceCell::new();
 
@@ -171,8 +201,9 @@ fn profile_dir_path(label: &str) -> PathBuf {
     p
 }
 
+// 加密保存的 profile 运行时写入解密目录，封存时随之删除
 fn chrome_log_path(label: &str) -> PathBuf {
-    profile_dir_path(label).join("chrome_debug.log")
+    vault::user_data_dir(label).join("chrome_debug.log")
 }
 
 fn profile_assets_meta_path(label: &str) -> PathBuf {
@@ -727,6 +758,14 @@ fn custom_icons_dir() -> PathBuf {
     base
 }
 
+// 生成唯一索引，基于标签哈希（0-999）
+fn icon_index_for_label(label: &str) -> usize {
+    let mut hasher = Sha256::new();
+    hasher.update(label.as_bytes());
+    let hash = hasher.finalize();
+    (hash[0] as usize) % 1000
+}
+
 fn generate_browser_icon(name: &str, index: usize) -> Result<PathBuf, String> {
     write_log(
         "INFO",
@@ -1224,8 +1263,324 @@ fn build_engine_launch_args(
     args
 }
 
+// 启动时的附加选项，随功能增加逐步扩展
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(default)]
+struct BrowserOpenOptions {
+    proxy: Option<String>,
+    restart_policy: Option<supervisor::RestartPolicy>,
+    remote_debugging: Option<bool>,
+}
+
+// 一次引擎启动所需的全部信息，崩溃重启与重新接管时复用同一份
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LaunchSpec {
+    bin: PathBuf,
+    // macOS 下通过 open 启动的 .app 目录
+    #[allow(dead_code)]
+    app_bundle: Option<PathBuf>,
+    args: Vec<String>,
+    #[serde(default)]
+    restart: supervisor::RestartPolicy,
+    #[serde(default)]
+    remote_debugging: bool,
+    // 额外环境变量，如按代理出口对齐的 TZ
+    #[serde(default)]
+    env: Vec<(String, String)>,
+}
+
+// 进程启动后 5s 内退出视为异常
+const ENGINE_QUICK_EXIT_MS: u128 = 5000;
+
+fn proxy_server_arg(proxy: &str) -> Option<String> {
+    let trimmed = proxy.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        // Chromium 不识别 socks5h，其 socks5 本身即由代理解析域名
+        let normalized = match trimmed.get(..10) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("socks5h://") => {
+                format!("socks5://{}", &trimmed[10..])
+            }
+            _ => trimmed.to_string(),
+        };
+        Some(format!("--proxy-server={}", normalized))
+    }
+}
+
+// Chromium 只认最后一个 --load-extension，多个扩展需合并为逗号分隔
+fn merge_load_extension_args(args: &mut Vec<String>) {
+    const FLAG: &str = "--load-extension=";
+    let dirs: Vec<String> = args
+        .iter()
+        .filter_map(|a| a.strip_prefix(FLAG).map(str::to_string))
+        .collect();
+    if dirs.len() < 2 {
+        return;
+    }
+    args.retain(|a| !a.starts_with(FLAG));
+    args.push(format!("{}{}", FLAG, dirs.join(",")));
+}
+
+fn write_pid_file(label: &str, pid: u32) {
+    let pid_file = profile_dir_path(label).join("pid");
+    match File::create(&pid_file) {
+        Ok(mut f) => {
+            let _ = write!(f, "{}", pid);
+            let _ = f.flush();
+            write_log(
+                "INFO",
+                &format!("wrote pid file {}", pid_file.to_string_lossy()),
+            );
+        }
+        Err(e) => {
+            write_log(
+                "ERROR",
+                &format!(
+                    "create pid file {} failed: {}",
+                    pid_file.to_string_lossy(),
+                    e
+                ),
+            );
+        }
+    }
+}
+
+// 返回 None 表示仍在运行；Some(status) 表示已退出（status 仅在自行 spawn 时可得）
+fn poll_engine_exit(label: &str, pid: u32) -> Option<Option<std::process::ExitStatus>> {
+    if let Ok(mut m) = proc_map().lock() {
+        if let Some(child) = m.get_mut(label) {
+            if child.id() == pid {
+                return match child.try_wait() {
+                    Ok(Some(status)) => Some(Some(status)),
+                    Ok(None) => None,
+                    Err(_) => Some(None),
+                };
+            }
+        }
+    }
+    if pid_alive_unix(pid) {
+        None
+    } else {
+        Some(None)
+    }
+}
+
+// 监控进程退出：早退时采集 Chromium 日志，随后交给 supervisor 决定是否重启
+pub(crate) fn spawn_exit_monitor(label: &str, pid: u32) {
+    let lbl = label.to_string();
+    thread::spawn(move || {
+        let start = std::time::Instant::now();
+        loop {
+            if let Some(status) = poll_engine_exit(&lbl, pid) {
+                let ms = start.elapsed().as_millis();
+                if ms < ENGINE_QUICK_EXIT_MS {
+                    write_log(
+                        "ERROR",
+                        &format!("engine pid={} exited quickly ({}ms)", pid, ms),
+                    );
+                    let logp = chrome_log_path(&lbl);
+                    if let Some(tail) = tail_file_lines(&logp, 100, 64 * 1024) {
+                        for line in tail.lines() {
+                            write_log("ERROR", &format!("[ChromeLog][{}] {}", lbl, line));
+                        }
+                    } else {
+                        write_log("WARN", &format!("no chrome_debug.log for {}", lbl));
+                    }
+                }
+                sessions::unregister(&lbl, Some(pid));
+                // 不再重启时才封存，重启会继续使用同一解密目录
+                if !supervisor::handle_engine_exit(&lbl, pid, status, start.elapsed()) {
+                    vault::seal_after_exit(&lbl);
+                    relay::stop_for_exit(&lbl);
+                    emulation::stop(&lbl);
+                }
+                break;
+            }
+            thread::sleep(Duration::from_millis(200));
+        }
+    });
+}
+
+// 启动未完成（中途出错或拿不到 pid）时撤销已做的准备，不留下解密目录与中转监听
+struct LaunchRollback<'a> {
+    label: &'a str,
+    started: bool,
+}
+
+impl Drop for LaunchRollback<'_> {
+    fn drop(&mut self) {
+        if self.started {
+            return;
+        }
+        write_log(
+            "WARN",
+            &format!("browser_open label={} did not start, rolling back", self.label),
+        );
+        vault::seal_after_exit(self.label);
+        relay::stop_for_exit(self.label);
+        emulation::stop(self.label);
+    }
+}
+
+// 按 LaunchSpec 启动引擎进程，写入 pid 文件并挂上退出监控
+fn launch_engine(label: &str, spec: &LaunchSpec) -> Result<Option<u32>, String> {
+    let args = &spec.args;
+    // 清掉上次运行遗留的调试端口文件，避免读到过期端口
+    let _ = fs::remove_file(sessions::devtools_port_file(label));
+    #[cfg(target_os = "macos")]
+    {
+        let Some(app_to_use) = spec.app_bundle.as_ref() else {
+            write_log("ERROR", "无法找到应用目录");
+            return Err("无法找到应用目录".into());
+        };
+        let profile_dir = vault::user_data_dir(label);
+
+        write_log(
+            "INFO",
+            &format!("open app {:?} with args={:?}", app_to_use, args),
+        );
+        let mut open_cmd = Command::new("open");
+        open_cmd.arg("-n");
+        for (k, v) in &spec.env {
+            open_cmd.arg("--env").arg(format!("{}={}", k, v));
+        }
+        let status = open_cmd
+            .arg(app_to_use)
+            .arg("--args")
+            .args(args)
+            .status();
+        match status {
+            Ok(st) if st.success() => {
+                // 轮询查找 PID
+                let pid = try_find_pid_by_profile(&profile_dir, 3000);
+                if let Some(pid) = pid {
+                    write_log("INFO", &format!("open ok pid={}", pid));
+                    // 先写入 pid 文件与监控，避免后续激活卡住影响前端状态
+                    write_pid_file(label, pid);
+                    sessions::register(label, pid, spec);
+                    supervisor::note_pid(label, pid);
+                    spawn_exit_monitor(label, pid);
+                    // 尝试前置激活窗口（异步，不阻塞主流程）
+                    if let Some(app_name_os) = app_to_use.file_name() {
+                        let mut app_name = app_name_os.to_string_lossy().to_string();
+                        if let Some(stripped) = app_name.strip_suffix(".app") {
+                            app_name = stripped.to_string();
+                        }
+                        match Command::new("osascript")
+                            .args([
+                                "-e",
+                                &format!("tell application \"{}\" to activate", app_name),
+                            ])
+                            .spawn()
+                        {
+                            Ok(_) => write_log(
+                                "INFO",
+                                &format!("osascript activate spawned for {}", app_name),
+                            ),
+                            Err(e) => {
+                                write_log(
+                                    "ERROR",
+                                    &format!(
+                                        "osascript spawn error: {} — fallback to direct spawn",
+                                        e
+                                    ),
+                                );
+                                let _ = Command::new(&spec.bin)
+                                    .args(args)
+                                    .stdin(Stdio::null())
+                                    .stdout(Stdio::null())
+                                    .stderr(Stdio::null())
+                                    .status()
+                                    .map(|s| {
+                                        write_log(
+                                            "INFO",
+                                            &format!("fallback direct spawn status={}", s),
+                                        )
+                                    })
+                                    .map_err(|e| {
+                                        write_log(
+                                            "ERROR",
+                                            &format!("fallback direct spawn error: {}", e),
+                                        )
+                                    });
+                            }
+                        }
+                    }
+                    return Ok(Some(pid));
+                } else {
+                    write_log("WARN", "open succeeded but pid not found within timeout");
+                    return Ok(None);
+                }
+            }
+            Ok(st) => {
+                write_log("ERROR", &format!("open returned status {}", st));
+            }
+            Err(e) => {
+                write_log("ERROR", &format!("open failed: {}", e));
+            }
+        }
+    }
+    // 非 macOS 或回退：直接执行二进制
+    write_log(
+        "INFO",
+        &format!("spawn engine: {:?} args={:?}", spec.bin, args),
+    );
+    let child = Command::new(&spec.bin)
+        .args(args)
+        .envs(spec.env.iter().map(|(k, v)| (k, v)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| {
+            let msg = format!("spawn failed: {e}");
+            write_log("ERROR", &msg);
+            msg
+        })?;
+    let pid = child.id();
+    write_log("INFO", &format!("spawn ok pid={}", pid));
+    if let Ok(mut m) = proc_map().lock() {
+        m.insert(label.to_string(), child);
+    }
+    write_pid_file(label, pid);
+    sessions::register(label, pid, spec);
+    supervisor::note_pid(label, pid);
+    spawn_exit_monitor(label, pid);
+    Ok(Some(pid))
+}
+
+// 启动可能要在队列中等待，放到阻塞线程池执行，避免占用主线程
 #[tauri::command]
-fn browser_open(
+#[allow(clippy::too_many_arguments)]
+async fn browser_open(
+    app: AppHandle,
+    label: String,
+    url: Option<String>,
+    version: Option<String>,
+    window_title: Option<String>,
+    browser_name: Option<String>,
+    disable_cors: Option<bool>,
+    options: Option<BrowserOpenOptions>,
+) -> Result<Option<u32>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        browser_open_blocking(
+            app,
+            &label,
+            url.as_deref(),
+            version.as_deref(),
+            window_title.as_deref(),
+            browser_name.as_deref(),
+            disable_cors,
+            options,
+        )
+    })
+    .await
+    .map_err(|e| format!("browser_open task failed: {e}"))?
+}
+
+#[allow(clippy::too_many_arguments)]
+fn browser_open_blocking(
     app: AppHandle,
     label: &str,
     url: Option<&str>,
@@ -1233,7 +1588,9 @@ fn browser_open(
     window_title: Option<&str>,
     browser_name: Option<&str>,
     disable_cors: Option<bool>,
+    options: Option<BrowserOpenOptions>,
 ) -> Result<Option<u32>, String> {
+    let mut opts = options.unwrap_or_default();
     let lbl = format!("browser-{}", label);
     let cleaned_window_title = window_title.and_then(|t| {
         let trimmed = t.trim();
@@ -1311,19 +1668,32 @@ fn browser_open(
         );
     }
     if let Some(bin) = engine_bin {
+        // 受运行数与并发启动数限制，名额在启动完成后释放；
+        // 排队在代理解析与检查之前，避免排队期间的会话轮换、代理检查与地理查询白做或过期
+        let _permit = launch_queue::acquire(Some(&app), label)?;
+        // proxy:/pool: 引用在启动时解析为具体代理地址
+        let resolved_proxy = proxies::resolve_for_launch(label, opts.proxy.as_deref())?;
+        opts.proxy = resolved_proxy.as_ref().map(|r| r.url.clone());
+        if app_settings::current().proxy_check_before_launch {
+            proxy_check::ensure_alive(label, resolved_proxy.as_ref())?;
+        }
+        // 有代理时按出口 IP 解析地理信息，失败不影响启动
+        let geo = geoip::resolve_for_launch(label, resolved_proxy.as_ref());
+        // 指纹中的网络信息按限速设置改写，需在指纹检查之前
+        let throttle = throttle::check_before_launch(label)?;
+        // 指纹自相矛盾时不启动
+        fingerprint_lint::check_before_launch(
+            label,
+            version,
+            geo.as_ref().map(|g| g.proxy_geo()).as_ref(),
+        )?;
         let profile_dir = profile_dir_path(label);
         let _ = fs::create_dir_all(&profile_dir);
         let mut profile_assets = ProfileAssetsMeta::default();
         // 生成自定义图标和App Bundle（仅在macOS上）
         #[allow(unused_variables)]
         let (custom_app, app_dir) = if cfg!(target_os = "macos") {
-            // 生成唯一索引，基于标签哈希
-            let mut hasher = Sha256::new();
-            hasher.update(label.as_bytes());
-            let hash = hasher.finalize();
-            let icon_index = (hash[0] as usize) % 1000; // 0-999的索引
-
-            match generate_browser_icon(&display_name, icon_index) {
+            match generate_browser_icon(&display_name, icon_index_for_label(label)) {
                 Ok(icon_path) => {
                     profile_assets.custom_icon_path = Some(icon_path.to_string_lossy().to_string());
                     // 创建自定义App Bundle
@@ -1422,11 +1792,17 @@ fn browser_open(
             }
         }
         write_profile_assets_meta(label, &profile_assets);
-        let crash_dir = profile_dir.join("crashes");
+        // 加密保存的 profile 先解密到临时目录
+        let user_data_dir = vault::prepare_user_data_dir(label)?;
+        let mut rollback = LaunchRollback {
+            label,
+            started: false,
+        };
+        let crash_dir = user_data_dir.join("crashes");
         ensure_dir(&crash_dir);
         let log_file = chrome_log_path(label);
-        let args = build_engine_launch_args(
-            &profile_dir,
+        let mut args = build_engine_launch_args(
+            &user_data_dir,
             &crash_dir,
             &log_file,
             want_restore,
@@ -1434,224 +1810,93 @@ fn browser_open(
             &display_title,
             disable_cors_enabled,
         );
-        #[cfg(target_os = "macos")]
-        {
-            // 优先通过 open 打开自定义或原始 .app，避免 GUI 激活问题
-            let app_to_use = if let Some(ref custom_app) = custom_app.as_ref() {
-                custom_app
-            } else if let Some(app_dir) = bin
-                .parent()
+        // 经本地中转时浏览器只连接 127.0.0.1，由中转认证并统计流量
+        let engine_proxy = relay::prepare_launch(
+            label,
+            &user_data_dir,
+            resolved_proxy.as_ref(),
+            throttle.is_some(),
+        )?;
+        if let Some(p) = engine_proxy.as_ref() {
+            args.extend(proxy_server_arg(&p.server));
+            args.extend(p.args.iter().cloned());
+        }
+        args.extend(dns::prepare_launch(
+            label,
+            &user_data_dir,
+            resolved_proxy.as_ref().map(|r| r.url.as_str()),
+            engine_proxy.as_ref().map(|p| p.server.as_str()),
+        )?);
+        args.extend(webrtc::prepare_launch(
+            label,
+            &user_data_dir,
+            resolved_proxy
+                .as_ref()
+                .and(engine_proxy.as_ref().map(|p| p.url.as_str())),
+            geo.as_ref().map(|g| g.ip.as_str()),
+        ));
+        args.extend(policy::prepare_launch(label)?);
+        args.extend(request_rules::prepare_launch(label, &user_data_dir)?);
+        let env = geo.as_ref().map(geoip::launch_env).unwrap_or_default();
+        if let Some(g) = geo.as_ref() {
+            args.extend(geoip::launch_args(g));
+        }
+        let remote_debugging = opts.remote_debugging.unwrap_or(false);
+        if remote_debugging {
+            // 端口由 Chromium 自选，实际地址写入 user-data-dir/DevToolsActivePort
+            args.push("--remote-debugging-port=0".into());
+        }
+        // 未经中转时只能通过 CDP 限速
+        let cdp_throttle = throttle.filter(|_| !relay::is_running(label));
+        if cdp_throttle.is_some() && !remote_debugging {
+            return Err(format!(
+                "network throttling of {} requires the local relay or remote debugging",
+                label
+            ));
+        }
+        // macOS 优先通过 open 打开自定义或原始 .app，避免 GUI 激活问题
+        let app_bundle = custom_app.or_else(|| {
+            bin.parent()
                 .and_then(|p| p.parent())
                 .and_then(|p| p.parent())
-            {
-                app_dir
-            } else {
-                write_log("ERROR", "无法找到应用目录");
-                return Err("无法找到应用目录".into());
-            };
-
-            write_log(
-                "INFO",
-                &format!("open app {:?} with args={:?}", app_to_use, args),
-            );
-            let status = Command::new("open")
-                .arg("-n")
-                .arg(app_to_use)
-                .arg("--args")
-                .args(&args)
-                .status();
-            match status {
-                Ok(st) if st.success() => {
-                    // 轮询查找 PID
-                    let pid = try_find_pid_by_profile(&profile_dir, 3000);
-                    if let Some(pid) = pid {
-                        write_log("INFO", &format!("open ok pid={}", pid));
-                        // 先写入 pid 文件与监控，避免后续激活卡住影响前端状态
-                        let pid_file = profile_dir_path(label).join("pid");
-                        match File::create(&pid_file) {
-                            Ok(mut f) => {
-                                let _ = write!(f, "{}", pid);
-                                let _ = f.flush();
-                                write_log(
-                                    "INFO",
-                                    &format!("wrote pid file {}", pid_file.to_string_lossy()),
-                                );
-                            }
-                            Err(e) => {
-                                write_log(
-                                    "ERROR",
-                                    &format!(
-                                        "create pid file {} failed: {}",
-                                        pid_file.to_string_lossy(),
-                                        e
-                                    ),
-                                );
-                            }
-                        }
-                        // 监控进程早退，采集 Chromium 日志
-                        let lbl = label.to_string();
-                        let pdir = profile_dir.clone();
-                        thread::spawn(move || {
-                            let start = std::time::Instant::now();
-                            loop {
-                                if !pid_alive_unix(pid) {
-                                    let ms = start.elapsed().as_millis();
-                                    if ms < 5000 {
-                                        // 5s 内退出认为异常
-                                        write_log(
-                                            "ERROR",
-                                            &format!(
-                                                "engine pid={} exited quickly ({}ms)",
-                                                pid, ms
-                                            ),
-                                        );
-                                        let logp = pdir.join("chrome_debug.log");
-                                        if let Some(tail) = tail_file_lines(&logp, 100, 64 * 1024) {
-                                            for line in tail.lines() {
-                                                write_log(
-                                                    "ERROR",
-                                                    &format!("[ChromeLog][{}] {}", lbl, line),
-                                                );
-                                            }
-                                        } else {
-                                            write_log(
-                                                "WARN",
-                                                &format!("no chrome_debug.log for {}", lbl),
-                                            );
-                                        }
-                                    }
-                                    break;
-                                }
-                                thread::sleep(Duration::from_millis(200));
-                            }
-                        });
-                        // 尝试前置激活窗口（异步，不阻塞主流程）
-                        if let Some(app_name_os) = app_to_use.file_name() {
-                            let mut app_name = app_name_os.to_string_lossy().to_string();
-                            if let Some(stripped) = app_name.strip_suffix(".app") {
-                                app_name = stripped.to_string();
-                            }
-                            match Command::new("osascript")
-                                .args([
-                                    "-e",
-                                    &format!("tell application \"{}\" to activate", app_name),
-                                ])
-                                .spawn()
-                            {
-                                Ok(_) => write_log(
-                                    "INFO",
-                                    &format!("osascript activate spawned for {}", app_name),
-                                ),
-                                Err(e) => {
-                                    write_log(
-                                        "ERROR",
-                                        &format!(
-                                            "osascript spawn error: {} — fallback to direct spawn",
-                                            e
-                                        ),
-                                    );
-                                    let _ = Command::new(&bin)
-                                        .args(&args)
-                                        .stdin(Stdio::null())
-                                        .stdout(Stdio::null())
-                                        .stderr(Stdio::null())
-                                        .status()
-                                        .map(|s| {
-                                            write_log(
-                                                "INFO",
-                                                &format!("fallback direct spawn status={}", s),
-                                            )
-                                        })
-                                        .map_err(|e| {
-                                            write_log(
-                                                "ERROR",
-                                                &format!("fallback direct spawn error: {}", e),
-                                            )
-                                        });
-                                }
-                            }
-                        }
-                        return Ok(Some(pid));
-                    } else {
-                        write_log("WARN", "open succeeded but pid not found within timeout");
-                        return Ok(None);
-                    }
+                .map(|p| p.to_path_buf())
+        });
+        merge_load_extension_args(&mut args);
+        let spec = LaunchSpec {
+            bin,
+            app_bundle,
+            args,
+            restart: opts.restart_policy.unwrap_or_default(),
+            remote_debugging,
+            env,
+        };
+        // 先登记再启动，pid 由 launch_engine 在挂上退出监控前写入
+        supervisor::track(label, 0, spec.clone());
+        let pid = launch_engine(label, &spec).inspect_err(|_| supervisor::untrack(label))?;
+        run_history::record(
+            label,
+            run_history::RunRecord {
+                started_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                pid,
+                engine_version: version.map(str::to_string),
+                proxy_id: resolved_proxy.as_ref().and_then(|r| r.proxy_id.clone()),
+                proxy_username: resolved_proxy.and_then(|r| r.username),
+                exit_ip: geo.as_ref().map(|g| g.ip.clone()),
+            },
+        );
+        match pid {
+            Some(_) => {
+                rollback.started = true;
+                if let Some(g) = geo {
+                    geoip::report_applied(&app, label, g, remote_debugging);
                 }
-                Ok(st) => {
-                    write_log("ERROR", &format!("open returned status {}", st));
+                if let Some(t) = cdp_throttle {
+                    throttle::apply_after_launch(label, t);
                 }
-                Err(e) => {
-                    write_log("ERROR", &format!("open failed: {}", e));
-                }
-            }
-        }
-        // 非 macOS 或回退：直接执行二进制
-        write_log("INFO", &format!("spawn engine: {:?} args={:?}", bin, args));
-        let child = Command::new(bin)
-            .args(&args)
-            .stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn()
-            .map_err(|e| {
-                let msg = format!("spawn failed: {e}");
-                write_log("ERROR", &msg);
-                msg
-            })?;
-        let pid = child.id();
-        write_log("INFO", &format!("spawn ok pid={}", pid));
-        if let Ok(mut m) = proc_map().lock() {
-            m.insert(label.to_string(), child);
-        }
-        let pid_file = profile_dir_path(label).join("pid");
-        match File::create(&pid_file) {
-            Ok(mut f) => {
-                let _ = write!(f, "{}", pid);
-                let _ = f.flush();
-                write_log(
-                    "INFO",
-                    &format!("wrote pid file {}", pid_file.to_string_lossy()),
-                );
-            }
-            Err(e) => {
-                write_log(
-                    "ERROR",
-                    &format!(
-                        "create pid file {} failed: {}",
-                        pid_file.to_string_lossy(),
-                        e
-                    ),
-                );
             }
+            None => supervisor::untrack(label),
         }
-        // 监控进程早退，采集 Chromium 日志
-        let lbl = label.to_string();
-        let pdir = profile_dir.clone();
-        thread::spawn(move || {
-            let start = std::time::Instant::now();
-            loop {
-                if !pid_alive_unix(pid) {
-                    let ms = start.elapsed().as_millis();
-                    if ms < 5000 {
-                        write_log(
-                            "ERROR",
-                            &format!("engine pid={} exited quickly ({}ms)", pid, ms),
-                        );
-                        let logp = pdir.join("chrome_debug.log");
-                        if let Some(tail) = tail_file_lines(&logp, 100, 64 * 1024) {
-                            for line in tail.lines() {
-                                write_log("ERROR", &format!("[ChromeLog][{}] {}", lbl, line));
-                            }
-                        } else {
-                            write_log("WARN", &format!("no chrome_debug.log for {}", lbl));
-                        }
-                    }
-                    break;
-                }
-                thread::sleep(Duration::from_millis(200));
-            }
-        });
-        return Ok(Some(pid));
+        return Ok(pid);
     }
     // Fallback: open internal webview
     if app.get_webview_window(&lbl).is_some() {
@@ -1672,12 +1917,19 @@ fn browser_open(
 
 #[tauri::command]
 fn browser_close(app: AppHandle, label: &str) -> Result<(), String> {
+    // 主动关闭，不应触发自动重启
+    supervisor::note_close_requested(label);
+    har::save_before_close(label);
+    sessions::unregister(label, None);
     // Close spawned engine process if any
     if let Ok(mut m) = proc_map().lock() {
         if let Some(mut child) = m.remove(label) {
             let _ = child.kill();
             let _ = child.wait();
             let _ = fs::remove_file(profile_dir_path(label).join("pid"));
+            vault::seal_after_exit(label);
+            relay::stop_for_exit(label);
+            emulation::stop(label);
             return Ok(());
         }
     }
@@ -1712,6 +1964,9 @@ fn browser_close(app: AppHandle, label: &str) -> Result<(), String> {
                         .status();
                 }
                 let _ = fs::remove_file(&pid_path);
+                vault::seal_after_exit(label);
+                relay::stop_for_exit(label);
+                emulation::stop(label);
                 write_log("INFO", &format!("browser_close label={} done", label));
                 return Ok(());
             }
@@ -1738,6 +1993,8 @@ fn browser_delete(app: AppHandle, label: &str) -> Result<CacheCleanupSummary, St
             .push(format!("close before delete failed label={} err={}", label, e));
     }
 
+    vault::discard(label);
+    snapshots::discard(label);
     merge_cleanup_summary(&mut summary, cleanup_profile_files(label));
 
     let active_labels = collect_existing_profile_labels();
@@ -1866,7 +2123,8 @@ fn browser_running(label: &str) -> Option<u32> {
     None
 }
 
-fn shutdown_all_browsers(app: &AppHandle) {
+// 收集当前可能仍在运行的浏览器 label（自行 spawn、pid 文件、内置 webview）
+fn running_browser_labels(app: &AppHandle) -> HashSet<String> {
     let mut labels: HashSet<String> = HashSet::new();
 
     if let Ok(m) = proc_map().lock() {
@@ -1877,11 +2135,9 @@ fn shutdown_all_browsers(app: &AppHandle) {
     if let Ok(entries) = fs::read_dir(&profiles_root) {
         for entry in entries.flatten() {
             let path = entry.path();
-            if path.is_dir() {
-                if path.join("pid").exists() {
-                    if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                        labels.insert(name.to_string());
-                    }
+            if path.is_dir() && path.join("pid").exists() {
+                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                    labels.insert(name.to_string());
                 }
             }
         }
@@ -1892,24 +2148,196 @@ fn shutdown_all_browsers(app: &AppHandle) {
             labels.insert(stripped.to_string());
         }
     }
+    labels
+}
+
+// 关闭超时时直接结束进程并封存，管理器退出后不残留浏览器或明文数据
+fn force_kill_browser(label: &str) {
+    if let Some(pid) = browser_running(label) {
+        #[cfg(target_family = "unix")]
+        let _ = Command::new("kill")
+            .args(["-KILL", &pid.to_string()])
+            .status();
+        #[cfg(not(target_family = "unix"))]
+        let _ = Command::new("taskkill")
+            .args(["/F", "/T", "/PID", &pid.to_string()])
+            .status();
+        let start = std::time::Instant::now();
+        while pid_alive_unix(pid) && start.elapsed() < Duration::from_millis(2000) {
+            thread::sleep(Duration::from_millis(100));
+        }
+        // 进程仍在写目录时打包会得到不完整的数据，留给下次启动时的残留封存处理
+        if pid_alive_unix(pid) {
+            write_log(
+                "ERROR",
+                &format!(
+                    "force kill label={} pid={} still alive, vault left mounted",
+                    label, pid
+                ),
+            );
+            relay::stop_for_exit(label);
+            emulation::stop(label);
+            return;
+        }
+    }
+    let _ = fs::remove_file(profile_dir_path(label).join("pid"));
+    vault::seal_after_exit(label);
+    relay::stop_for_exit(label);
+    emulation::stop(label);
+}
 
+// 并行关闭所有浏览器，整体超过 shutdown_timeout_ms 后不再等待
+fn shutdown_all_browsers(app: &AppHandle) {
+    let labels = running_browser_labels(app);
     if labels.is_empty() {
+        traffic::flush();
         return;
     }
 
+    let timeout = Duration::from_millis(app_settings::current().shutdown_timeout_ms);
     write_log(
         "INFO",
-        &format!("shutdown_all_browsers closing {} labels", labels.len()),
+        &format!(
+            "shutdown_all_browsers closing {} labels timeout={}ms",
+            labels.len(),
+            timeout.as_millis()
+        ),
     );
 
-    for label in labels.into_iter() {
-        if let Err(e) = browser_close(app.clone(), &label) {
-            write_log(
-                "ERROR",
-                &format!("shutdown_all_browsers {} failed: {}", label, e),
-            );
+    let (tx, rx) = std::sync::mpsc::channel();
+    for label in labels.iter().cloned() {
+        let app = app.clone();
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let res = browser_close(app, &label);
+            let _ = tx.send((label, res));
+        });
+    }
+    drop(tx);
+
+    let deadline = std::time::Instant::now() + timeout;
+    let mut pending = labels;
+    while !pending.is_empty() {
+        let left = deadline.saturating_duration_since(std::time::Instant::now());
+        match rx.recv_timeout(left) {
+            Ok((label, res)) => {
+                if let Err(e) = res {
+                    write_log(
+                        "ERROR",
+                        &format!("shutdown_all_browsers {} failed: {}", label, e),
+                    );
+                }
+                pending.remove(&label);
+            }
+            Err(_) => break,
+        }
+    }
+    if !pending.is_empty() {
+        let mut rest: Vec<_> = pending.into_iter().collect();
+        rest.sort();
+        write_log(
+            "WARN",
+            &format!(
+                "shutdown_all_browsers timed out, force killing: {}",
+                rest.join(",")
+            ),
+        );
+        for label in &rest {
+            force_kill_browser(label);
         }
     }
+    // 中转连接随浏览器关闭结束，退出前把未落盘的流量写入账本
+    traffic::flush();
+}
+
+// 保留浏览器继续运行；pid 文件与 launch.json 保留，下次启动时重新接管
+fn detach_all_browsers(app: &AppHandle) {
+    let mut labels: Vec<_> = running_browser_labels(app).into_iter().collect();
+    if labels.is_empty() {
+        return;
+    }
+    labels.sort();
+    write_log(
+        "INFO",
+        &format!(
+            "leaving {} browsers running detached: {}",
+            labels.len(),
+            labels.join(",")
+        ),
+    );
+}
+
+// 用户在询问弹窗中做出的选择，仅对本次退出生效
+static EXIT_DECISION: OnceCell<app_settings::ExitBehavior> = OnceCell::new();
+static EXIT_APPLIED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+#[derive(Debug, Serialize, Clone)]
+struct ExitAskPayload {
+    running: usize,
+}
+
+// 返回 None 表示需要询问用户
+fn resolve_exit_behavior() -> Option<app_settings::ExitBehavior> {
+    if let Some(decision) = EXIT_DECISION.get() {
+        return Some(*decision);
+    }
+    match app_settings::current().exit_behavior {
+        app_settings::ExitBehavior::Ask => None,
+        behavior => Some(behavior),
+    }
+}
+
+fn apply_exit_behavior(app: &AppHandle, behavior: app_settings::ExitBehavior) {
+    if EXIT_APPLIED.swap(true, std::sync::atomic::Ordering::SeqCst) {
+        return;
+    }
+    match behavior {
+        app_settings::ExitBehavior::CloseAll => shutdown_all_browsers(app),
+        app_settings::ExitBehavior::Detach => detach_all_browsers(app),
+        app_settings::ExitBehavior::Ask => {}
+    }
+}
+
+// 返回 false 表示已转交前端询问，本次关闭/退出应被阻止
+fn handle_exit_request(app: &AppHandle) -> bool {
+    if let Some(behavior) = resolve_exit_behavior() {
+        apply_exit_behavior(app, behavior);
+        return true;
+    }
+    let running = running_browser_labels(app).len();
+    if running == 0 {
+        return true;
+    }
+    write_log(
+        "INFO",
+        &format!("exit requested with {} running browsers, asking", running),
+    );
+    if let Err(e) = app.emit("manager-exit-ask", ExitAskPayload { running }) {
+        write_log("ERROR", &format!("emit manager-exit-ask failed: {}", e));
+        return true;
+    }
+    false
+}
+
+#[tauri::command]
+fn app_exit(
+    app: AppHandle,
+    behavior: app_settings::ExitBehavior,
+    remember: Option<bool>,
+) -> Result<(), String> {
+    if behavior == app_settings::ExitBehavior::Ask {
+        return Err("behavior must be close_all or detach".into());
+    }
+    if remember.unwrap_or(false) {
+        let mut settings = app_settings::current();
+        settings.exit_behavior = behavior;
+        app_settings::app_settings_update(settings)?;
+    }
+    let _ = EXIT_DECISION.set(behavior);
+    write_log("INFO", &format!("app_exit behavior={:?}", behavior));
+    traffic::flush();
+    app.exit(0);
+    Ok(())
 }
 
 #[cfg(target_os = "macos")]
@@ -1997,6 +2425,33 @@ mod browser_open_tests {
         assert!(!args.contains(&"--disable-web-security".to_string()));
         assert!(!args.contains(&"--disable-site-isolation-trials".to_string()));
     }
+
+    #[test]
+    fn test_proxy_server_arg_normalizes_socks5h() {
+        assert_eq!(
+            proxy_server_arg(" SOCKS5H://10.0.0.1:1080 ").as_deref(),
+            Some("--proxy-server=socks5://10.0.0.1:1080")
+        );
+        assert_eq!(
+            proxy_server_arg("http://10.0.0.1:8080").as_deref(),
+            Some("--proxy-server=http://10.0.0.1:8080")
+        );
+        assert_eq!(proxy_server_arg("  "), None);
+    }
+
+    #[test]
+    fn test_merge_load_extension_args() {
+        let mut args = vec![
+            "--load-extension=/p/webrtc".to_string(),
+            "--lang=en".to_string(),
+            "--load-extension=/p/request_rules".to_string(),
+        ];
+        merge_load_extension_args(&mut args);
+        assert_eq!(
+            args,
+            ["--lang=en", "--load-extension=/p/webrtc,/p/request_rules"]
+        );
+    }
 }
 
 #[cfg(test)]
@@ -2007,16 +2462,19 @@ pub fn run() {
     // prepare logging
     let _ = rotate_logs();
     write_log("INFO", "应用启动");
+    // 重新接管上次未关闭的浏览器
+    sessions::reconcile();
+    stats::start_collector();
+    traffic::start_flusher();
 
     let context = tauri::generate_context!();
     let app = tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_http::init())
         .on_window_event(|window, event| {
-            if let WindowEvent::CloseRequested { .. } = event {
-                if window.label() == "main" {
-                    let handle = window.app_handle();
-                    shutdown_all_browsers(&handle);
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                if window.label() == "main" && !handle_exit_request(window.app_handle()) {
+                    api.prevent_close();
                 }
             }
         })
@@ -2034,14 +2492,67 @@ pub fn run() {
             browser_exists,
             browser_running,
             cleanup_stale_browser_cache,
+            sessions::browser_session_info,
+            sessions::browser_sessions,
+            sessions::browser_reconcile,
+            sessions::browser_reconcile_report,
+            app_settings::app_settings_get,
+            app_settings::app_settings_update,
+            launch_queue::browser_launch_cancel,
+            launch_queue::browser_launch_queue,
+            stats::browser_stats,
+            stats::browser_stats_all,
+            profile_store::profile_config_get,
+            profile_store::profile_config_save,
+            profile_archive::profile_export,
+            profile_archive::profile_import,
+            profile_clone::profile_clone,
+            fingerprint::fingerprint_generate,
+            fingerprint_lint::fingerprint_validate,
+            geoip::proxy_geo_lookup,
+            geoip::browser_geo_applied,
+            har::browser_har_start,
+            har::browser_har_stop,
+            policy::profile_policies_preview,
+            proxies::proxy_list,
+            proxies::proxy_save,
+            proxies::proxy_delete,
+            proxies::proxy_pool_save,
+            proxies::proxy_pool_delete,
+            proxy_check::proxy_check,
+            proxy_check::proxy_check_bulk,
+            proxy_import::proxy_import,
+            proxy_import::proxy_export,
+            proxy_session::proxy_session_rotate,
+            run_history::profile_run_history,
+            traffic::proxy_usage,
+            throttle::throttle_connection,
+            snapshots::profile_snapshot_create,
+            snapshots::profile_snapshot_list,
+            snapshots::profile_snapshot_restore,
+            snapshots::profile_snapshot_delete,
+            cookies::profile_cookies_import,
+            cookies::profile_cookies_export,
+            vault::vault_status,
+            vault::vault_unlock,
+            vault::vault_lock,
+            vault::vault_set_passphrase,
+            vault::vault_enable,
+            vault::vault_disable,
+            app_exit,
             read_logs_tail
         ])
         .build(context)
         .expect("error while building tauri application");
 
     app.run(|app_handle, event| match event {
-        RunEvent::ExitRequested { .. } | RunEvent::Exit => {
-            shutdown_all_browsers(&app_handle);
+        RunEvent::ExitRequested { api, .. } if !handle_exit_request(app_handle) => {
+            api.prevent_exit();
+        }
+        RunEvent::Exit => {
+            // 已无法再询问时保守处理：保留浏览器运行
+            let behavior = resolve_exit_behavior().unwrap_or(app_settings::ExitBehavior::Detach);
+            apply_exit_behavior(app_handle, behavior);
         }
         _ => {}
     });
@@ -0,0 +1,275 @@
+// 按 profile 生成 Chromium 企业策略，通过 --chrome-policy 传给引擎
+// Chromium 不读取 user-data-dir 下的策略文件，而系统策略目录对所有 profile 生效，因此只能走命令行；
+// 该参数仅在非官方构建中生效，官方 Chrome 会忽略，需要 DoH 等设置时改用 Local State（见 dns）
+use crate::dns;
+use crate::profile_store::{self, ProfileConfig};
+use crate::write_log;
+use crate::{relay, request_rules, webrtc};
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+const CHROME_WEB_STORE_UPDATE_URL: &str = "https://clients2.google.com/service/update2/crx";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DefaultSearch {
+    pub name: String,
+    pub keyword: Option<String>,
+    // 需包含 {searchTerms}
+    pub search_url: String,
+    pub suggest_url: Option<String>,
+}
+
+// profile.json 中的 policies 字段，未设置的项不生成对应策略
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ProfilePolicies {
+    pub homepage: Option<String>,
+    // 非空时启动改为打开这些页面，不再恢复上次会话
+    pub startup_urls: Vec<String>,
+    pub default_search: Option<DefaultSearch>,
+    pub password_manager: Option<bool>,
+    // off / automatic / secure
+    pub doh_mode: Option<String>,
+    pub doh_templates: Option<String>,
+    // 扩展 ID，或 "ID;更新地址"
+    pub extension_install: Vec<String>,
+    pub extension_block: Vec<String>,
+}
+
+fn non_empty(s: &Option<String>) -> Option<&str> {
+    s.as_deref().map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn trimmed_list(items: &[String]) -> Vec<String> {
+    items
+        .iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn valid_extension_id(id: &str) -> bool {
+    id.len() == 32 && id.chars().all(|c| ('a'..='p').contains(&c))
+}
+
+// 与 Chromium 相同：公钥 SHA-256 的前 16 字节，每 4 位映射为 a-p
+fn extension_id(key: &str) -> String {
+    let der = base64::engine::general_purpose::STANDARD
+        .decode(key)
+        .unwrap_or_default();
+    Sha256::digest(&der)[..16]
+        .iter()
+        .flat_map(|b| [b >> 4, b & 0x0f])
+        .map(|n| (b'a' + n) as char)
+        .collect()
+}
+
+// 启动时加载的内置扩展
+fn own_extension_ids() -> Vec<String> {
+    [
+        webrtc::FAKE_IP_EXTENSION_KEY,
+        request_rules::EXTENSION_KEY,
+        relay::AUTH_EXTENSION_KEY,
+    ]
+    .iter()
+    .map(|k| extension_id(k))
+    .collect()
+}
+
+fn forcelist_entry(raw: &str) -> Result<String, String> {
+    let (id, update_url) = raw
+        .split_once(';')
+        .unwrap_or((raw, CHROME_WEB_STORE_UPDATE_URL));
+    let id = id.trim();
+    if !valid_extension_id(id) {
+        return Err(format!("invalid extension id: {}", id));
+    }
+    Ok(format!("{};{}", id, update_url.trim()))
+}
+
+// 生成策略（按名称排序，输出稳定）；配置不合法时报错
+pub(crate) fn build(config: &ProfileConfig) -> Result<BTreeMap<String, Value>, String> {
+    let p = &config.policies;
+    let mut out = BTreeMap::new();
+
+    if let Some(handling) = webrtc::policy_from_config(config).ip_handling() {
+        out.insert("WebRtcIPHandling".into(), json!(handling));
+    }
+
+    if let Some(home) = non_empty(&p.homepage) {
+        out.insert("HomepageLocation".into(), json!(home));
+        out.insert("HomepageIsNewTabPage".into(), json!(false));
+        out.insert("ShowHomeButton".into(), json!(true));
+    }
+    let startup = trimmed_list(&p.startup_urls);
+    if !startup.is_empty() {
+        // 4 = 打开指定网址
+        out.insert("RestoreOnStartup".into(), json!(4));
+        out.insert("RestoreOnStartupURLs".into(), json!(startup));
+    }
+
+    if let Some(search) = &p.default_search {
+        let url = search.search_url.trim();
+        if !url.contains("{searchTerms}") {
+            return Err("default search url must contain {searchTerms}".into());
+        }
+        out.insert("DefaultSearchProviderEnabled".into(), json!(true));
+        out.insert(
+            "DefaultSearchProviderName".into(),
+            json!(search.name.trim()),
+        );
+        out.insert("DefaultSearchProviderSearchURL".into(), json!(url));
+        if let Some(k) = non_empty(&search.keyword) {
+            out.insert("DefaultSearchProviderKeyword".into(), json!(k));
+        }
+        if let Some(s) = non_empty(&search.suggest_url) {
+            out.insert("DefaultSearchProviderSuggestURL".into(), json!(s));
+        }
+    }
+
+    if let Some(enabled) = p.password_manager {
+        out.insert("PasswordManagerEnabled".into(), json!(enabled));
+    }
+
+    // dns 设置为 doh 时由其生成 DoH 策略，不能与策略中的 DoH 同时设置
+    if let Some(template) = dns::doh_template(&config.dns)? {
+        if non_empty(&p.doh_mode).is_some() {
+            return Err("DNS-over-HTTPS is configured in both dns and policies".into());
+        }
+        out.insert("DnsOverHttpsMode".into(), json!("secure"));
+        out.insert("DnsOverHttpsTemplates".into(), json!(template));
+    }
+    if let Some(mode) = non_empty(&p.doh_mode) {
+        if !matches!(mode, "off" | "automatic" | "secure") {
+            return Err(format!("invalid DNS-over-HTTPS mode: {}", mode));
+        }
+        out.insert("DnsOverHttpsMode".into(), json!(mode));
+        match non_empty(&p.doh_templates) {
+            Some(t) if mode != "off" => {
+                out.insert("DnsOverHttpsTemplates".into(), json!(t));
+            }
+            None if mode == "secure" => {
+                return Err("DNS-over-HTTPS secure mode requires a template".into());
+            }
+            _ => {}
+        }
+    }
+
+    let install = trimmed_list(&p.extension_install)
+        .iter()
+        .map(|e| forcelist_entry(e))
+        .collect::<Result<Vec<_>, _>>()?;
+    if !install.is_empty() {
+        out.insert("ExtensionInstallForcelist".into(), json!(install));
+    }
+    let block = trimmed_list(&p.extension_block);
+    if !block.is_empty() {
+        // 屏蔽列表（如 "*"）同样作用于 --load-extension 加载的扩展
+        out.insert(
+            "ExtensionInstallAllowlist".into(),
+            json!(own_extension_ids()),
+        );
+        out.insert("ExtensionInstallBlocklist".into(), json!(block));
+    }
+    Ok(out)
+}
+
+// 仅非官方构建识别 --chrome-policy
+pub(crate) fn launch_args(policies: &BTreeMap<String, Value>) -> Vec<String> {
+    if policies.is_empty() {
+        return Vec::new();
+    }
+    vec![format!(
+        "--chrome-policy={}",
+        serde_json::to_string(policies).unwrap_or_default()
+    )]
+}
+
+// 启动前调用：策略不合法时拒绝启动
+pub(crate) fn prepare_launch(label: &str) -> Result<Vec<String>, String> {
+    let config = profile_store::load(label).unwrap_or_default();
+    let policies =
+        build(&config).map_err(|e| format!("profile {} policy invalid: {}", label, e))?;
+    if !policies.is_empty() {
+        write_log(
+            "INFO",
+            &format!("policies label={} count={}", label, policies.len()),
+        );
+    }
+    Ok(launch_args(&policies))
+}
+
+#[tauri::command]
+pub(crate) fn profile_policies_preview(
+    config: ProfileConfig,
+) -> Result<BTreeMap<String, Value>, String> {
+    build(&config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full_config() -> ProfileConfig {
+        ProfileConfig {
+            id: "CHE-1".into(),
+            fingerprint: Some(r#"{"webrtcPolicy":"proxy_only"}"#.into()),
+            policies: ProfilePolicies {
+                homepage: Some("https://example.com/".into()),
+                startup_urls: vec!["https://example.com/start".into(), " ".into()],
+                default_search: Some(DefaultSearch {
+                    name: "DuckDuckGo".into(),
+                    keyword: Some("ddg".into()),
+                    search_url: "https://duckduckgo.com/?q={searchTerms}".into(),
+                    suggest_url: None,
+                }),
+                password_manager: Some(false),
+                doh_mode: Some("secure".into()),
+                doh_templates: Some("https://dns.example/dns-query{?dns}".into()),
+                extension_install: vec![
+                    "cjpalhdlnbpafiamejdnhcphjbkeiagm".into(),
+                    "aapbdbdomjkkjkaonfhkkikfgjllcleb;https://updates.example/crx".into(),
+                ],
+                extension_block: vec!["*".into()],
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_policy_snapshots() {
+        let empty = build(&ProfileConfig::default()).unwrap();
+        assert!(empty.is_empty());
+        assert!(launch_args(&empty).is_empty());
+
+        let full = build(&full_config()).unwrap();
+        assert_eq!(
+            serde_json::to_string_pretty(&full).unwrap(),
+            include_str!("policy_snapshots/full.json").trim_end()
+        );
+        let args = launch_args(&full);
+        assert_eq!(args.len(), 1);
+        let passed: Value =
+            serde_json::from_str(args[0].strip_prefix("--chrome-policy=").unwrap()).unwrap();
+        assert_eq!(passed, serde_json::to_value(&full).unwrap());
+        // 内置扩展的公钥必须能解出合法 ID
+        assert!(own_extension_ids().iter().all(|id| valid_extension_id(id)));
+    }
+
+    #[test]
+    fn test_invalid_policies_rejected() {
+        let mut cfg = full_config();
+        cfg.policies.default_search.as_mut().unwrap().search_url = "https://x.test/".into();
+        assert!(build(&cfg).is_err());
+        let mut cfg = full_config();
+        cfg.policies.doh_templates = None;
+        assert!(build(&cfg).is_err());
+        let mut cfg = full_config();
+        cfg.policies.extension_install = vec!["not-an-id".into()];
+        assert!(build(&cfg).is_err());
+    }
+}
@@ -0,0 +1,574 @@
+// profile 导出/导入：zip 内含 manifest.json、profile.json、assets.json 与 user-data/ 目录
+use crate::profile_store::{self, ProfileConfig, PROFILE_CONFIG_FILE};
+use crate::vault;
+use crate::{
+    browser_running, data_local_base, find_engine_binary_for_version, profile_dir_path,
+    profiles_dir_path, read_profile_assets_meta, write_log, write_profile_assets_meta,
+};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+const MANIFEST_FILE: &str = "manifest.json";
+const ASSETS_FILE: &str = "assets.json";
+const USER_DATA_PREFIX: &str = "user-data/";
+const FORMAT_VERSION: u32 = 1;
+
+// 运行期文件，任何情况下都不打包
+const RUNTIME_FILES: &[&str] = &[
+    "pid",
+    "launch.json",
+    "DevToolsActivePort",
+    "chrome_debug.log",
+    "SingletonLock",
+    "SingletonSocket",
+    "SingletonCookie",
+    "lockfile",
+    "RunningChromeVersion",
+    "crashes",
+];
+
+// 可重建的缓存目录，按目录名匹配任意层级
+const CACHE_DIRS: &[&str] = &[
+    "Cache",
+    "Code Cache",
+    "GPUCache",
+    "GrShaderCache",
+    "GraphiteDawnCache",
+    "DawnCache",
+    "DawnGraphiteCache",
+    "DawnWebGPUCache",
+    "ShaderCache",
+    "CacheStorage",
+    "ScriptCache",
+    "component_crx_cache",
+    "optimization_guide_model_store",
+];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ProfileExportOptions {
+    pub exclude_caches: bool,
+}
+
+impl Default for ProfileExportOptions {
+    fn default() -> Self {
+        Self {
+            exclude_caches: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileManifest {
+    pub format_version: u32,
+    pub label: String,
+    pub exported_at: String,
+    pub app_version: String,
+    pub engine_version: Option<String>,
+    // 导出机器上的路径，导入时据此改写绝对路径
+    pub source_base: String,
+    pub source_profile_dir: String,
+    pub exclude_caches: bool,
+    pub files: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileExportSummary {
+    pub path: String,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileImportResult {
+    pub label: String,
+    pub original_label: String,
+    pub renamed: bool,
+    pub config: Option<ProfileConfig>,
+    pub warnings: Vec<String>,
+}
+
+pub(crate) fn sha256_hex(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    format!("{:x}", hasher.finalize())
+}
+
+pub(crate) fn is_runtime_file(rel: &Path) -> bool {
+    rel.components()
+        .next()
+        .and_then(|c| c.as_os_str().to_str())
+        .map(|first| RUNTIME_FILES.contains(&first) || first.starts_with("Singleton"))
+        .unwrap_or(false)
+}
+
+// Windows 上打出的包可能以反斜杠分隔，统一成 zip 内的 /
+fn normalize_entry_path(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+pub(crate) fn is_cache_path(rel: &Path) -> bool {
+    rel.components().any(|c| {
+        c.as_os_str()
+            .to_str()
+            .map(|s| CACHE_DIRS.contains(&s))
+            .unwrap_or(false)
+    })
+}
+
+// 列出 profile 目录下需要保留的文件（相对路径），跳过运行期文件和符号链接
+pub(crate) fn collect_profile_files(
+    profile_dir: &Path,
+    exclude_caches: bool,
+) -> Result<Vec<PathBuf>, String> {
+    let mut out = Vec::new();
+    let mut stack = vec![profile_dir.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let entries = fs::read_dir(&dir).map_err(|e| format!("read dir failed: {e}"))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(rel) = path.strip_prefix(profile_dir) else {
+                continue;
+            };
+            if is_runtime_file(rel) || (exclude_caches && is_cache_path(rel)) {
+                continue;
+            }
+            let Ok(ft) = entry.file_type() else {
+                continue;
+            };
+            if ft.is_dir() {
+                stack.push(path);
+            } else if ft.is_file() {
+                out.push(rel.to_path_buf());
+            }
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+fn zip_name(rel: &Path) -> String {
+    rel.components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// 顶层的 profile.json / assets.json 单独存放，其余归入 user-data/
+fn archive_name(rel: &Path) -> String {
+    let name = zip_name(rel);
+    if name == PROFILE_CONFIG_FILE || name == ASSETS_FILE {
+        name
+    } else {
+        format!("{}{}", USER_DATA_PREFIX, name)
+    }
+}
+
+#[tauri::command]
+pub(crate) fn profile_export(
+    label: &str,
+    path: &str,
+    options: Option<ProfileExportOptions>,
+) -> Result<ProfileExportSummary, String> {
+    let opts = options.unwrap_or_default();
+    let profile_dir = profile_dir_path(label);
+    if !profile_dir.is_dir() {
+        return Err(format!("profile {} not found", label));
+    }
+    // 运行中的数据库文件可能处于半写入状态
+    if browser_running(label).is_some() {
+        return Err(format!(
+            "profile {} is running, close it before export",
+            label
+        ));
+    }
+    if vault::is_sealed(label) {
+        return Err(format!(
+            "profile {} is sealed in the vault, disable vault mode before export",
+            label
+        ));
+    }
+    let files = collect_profile_files(&profile_dir, opts.exclude_caches)?;
+    let out = File::create(path).map_err(|e| format!("create archive failed: {e}"))?;
+    let mut zip = zip::ZipWriter::new(out);
+    let file_opts =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut entries = Vec::with_capacity(files.len());
+    let mut bytes = 0u64;
+    for rel in &files {
+        let data = fs::read(profile_dir.join(rel))
+            .map_err(|e| format!("read profile file failed: {e}"))?;
+        let name = archive_name(rel);
+        zip.start_file(name.as_str(), file_opts)
+            .map_err(|e| format!("zip start file failed: {e}"))?;
+        zip.write_all(&data)
+            .map_err(|e| format!("zip write failed: {e}"))?;
+        bytes += data.len() as u64;
+        entries.push(ManifestEntry {
+            path: name,
+            size: data.len() as u64,
+            sha256: sha256_hex(&data),
+        });
+    }
+    let manifest = ProfileManifest {
+        format_version: FORMAT_VERSION,
+        label: label.to_string(),
+        exported_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        engine_version: profile_store::load(label).and_then(|c| c.engine_version),
+        source_base: data_local_base().to_string_lossy().to_string(),
+        source_profile_dir: profile_dir.to_string_lossy().to_string(),
+        exclude_caches: opts.exclude_caches,
+        files: entries,
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("serialize manifest failed: {e}"))?;
+    zip.start_file(MANIFEST_FILE, file_opts)
+        .map_err(|e| format!("zip start file failed: {e}"))?;
+    zip.write_all(&manifest_json)
+        .map_err(|e| format!("zip write failed: {e}"))?;
+    zip.finish()
+        .map_err(|e| format!("zip finish failed: {e}"))?;
+    write_log(
+        "INFO",
+        &format!(
+            "profile_export label={} path={} files={} bytes={} exclude_caches={}",
+            label,
+            path,
+            manifest.files.len(),
+            bytes,
+            opts.exclude_caches
+        ),
+    );
+    Ok(ProfileExportSummary {
+        path: path.to_string(),
+        files: manifest.files.len(),
+        bytes,
+    })
+}
+
+fn validate_manifest(manifest: &ProfileManifest) -> Result<(), String> {
+    if manifest.format_version != FORMAT_VERSION {
+        return Err(format!(
+            "unsupported archive format version {}",
+            manifest.format_version
+        ));
+    }
+    if manifest.label.trim().is_empty() {
+        return Err("manifest label is empty".into());
+    }
+    // label 会直接拼成 profile 目录名
+    let label = &manifest.label;
+    if label.starts_with('.') || label.contains("..") || label.contains(['/', '\\', ':', '\0']) {
+        return Err(format!("unsafe label in manifest: {}", label));
+    }
+    for f in &manifest.files {
+        let p = normalize_entry_path(&f.path);
+        // 盘符与前导 / 在任一平台都视为绝对路径
+        if p.starts_with('/') || p.contains(':') || p.split('/').any(|c| c == "..") {
+            return Err(format!("unsafe path in manifest: {}", f.path));
+        }
+    }
+    Ok(())
+}
+
+// 原 label 已存在时依次尝试 label-2、label-3 ...
+pub(crate) fn allocate_label(base: &str, exists: impl Fn(&str) -> bool) -> String {
+    if !exists(base) {
+        return base.to_string();
+    }
+    let mut n = 2;
+    loop {
+        let candidate = format!("{}-{}", base, n);
+        if !exists(&candidate) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+// 导出机器 data 目录下的路径改写到本机；改写后不存在则清空，下次启动时重新生成
+fn rebase_asset_path(path: Option<String>, source_base: &str, local_base: &Path) -> Option<String> {
+    let p = PathBuf::from(path?);
+    let rel = p.strip_prefix(source_base).ok()?;
+    let local = local_base.join(rel);
+    if local.exists() {
+        Some(local.to_string_lossy().to_string())
+    } else {
+        None
+    }
+}
+
+// Preferences 等 JSON 里可能记录了 user-data-dir 的绝对路径
+pub(crate) fn rewrite_profile_dir_refs(content: &str, old_dir: &str, new_dir: &str) -> String {
+    let escape = |s: &str| {
+        serde_json::to_string(s)
+            .map(|q| q[1..q.len() - 1].to_string())
+            .unwrap_or_else(|_| s.to_string())
+    };
+    content.replace(&escape(old_dir), &escape(new_dir))
+}
+
+fn read_manifest<R: Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+) -> Result<ProfileManifest, String> {
+    let mut f = archive
+        .by_name(MANIFEST_FILE)
+        .map_err(|_| "manifest.json missing in archive".to_string())?;
+    let mut s = String::new();
+    f.read_to_string(&mut s)
+        .map_err(|e| format!("read manifest failed: {e}"))?;
+    serde_json::from_str(&s).map_err(|e| format!("parse manifest failed: {e}"))
+}
+
+fn extract_verified<R: Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    manifest: &ProfileManifest,
+    dest: &Path,
+) -> Result<(), String> {
+    for entry in &manifest.files {
+        let name = normalize_entry_path(&entry.path);
+        let rel = name.strip_prefix(USER_DATA_PREFIX).unwrap_or(&name);
+        // 旧版导出或手工打包的文件里可能混入运行期文件，解压后会让浏览器误判已在运行
+        if is_runtime_file(Path::new(rel)) {
+            continue;
+        }
+        let mut f = archive
+            .by_name(&entry.path)
+            .map_err(|_| format!("archive missing file {}", entry.path))?;
+        // size 来自包内清单，不能据此预分配；多读一个字节以发现超长内容
+        let mut data = Vec::new();
+        f.by_ref()
+            .take(entry.size.saturating_add(1))
+            .read_to_end(&mut data)
+            .map_err(|e| format!("read {} failed: {e}", entry.path))?;
+        if data.len() as u64 != entry.size {
+            return Err(format!("size mismatch for {}", entry.path));
+        }
+        if sha256_hex(&data) != entry.sha256 {
+            return Err(format!("checksum mismatch for {}", entry.path));
+        }
+        let out = dest.join(rel);
+        if let Some(parent) = out.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("mkdir failed: {e}"))?;
+        }
+        fs::write(&out, &data).map_err(|e| format!("write {} failed: {e}", rel))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) fn profile_import(path: &str) -> Result<ProfileImportResult, String> {
+    let file = File::open(path).map_err(|e| format!("open archive failed: {e}"))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("open zip failed: {e}"))?;
+    let manifest = read_manifest(&mut archive)?;
+    validate_manifest(&manifest)?;
+    let mut warnings = Vec::new();
+
+    let label = allocate_label(&manifest.label, |l| profile_dir_path(l).exists());
+    let root = profiles_dir_path();
+    fs::create_dir_all(&root).map_err(|e| format!("create profiles dir failed: {e}"))?;
+    // 先解压到临时目录，校验通过后再改名，避免留下半成品
+    let staging = root.join(format!(
+        ".import-{}",
+        Local::now().format("%Y%m%d%H%M%S%3f")
+    ));
+    if let Err(e) = extract_verified(&mut archive, &manifest, &staging) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    let dest = profile_dir_path(&label);
+    fs::rename(&staging, &dest).map_err(|e| {
+        let _ = fs::remove_dir_all(&staging);
+        format!("move imported profile failed: {e}")
+    })?;
+
+    let new_dir = dest.to_string_lossy().to_string();
+    for rel in ["Local State", "Default/Preferences"] {
+        let p = dest.join(rel);
+        if let Ok(content) = fs::read_to_string(&p) {
+            let rewritten =
+                rewrite_profile_dir_refs(&content, &manifest.source_profile_dir, &new_dir);
+            if rewritten != content {
+                if let Err(e) = fs::write(&p, rewritten) {
+                    warnings.push(format!("rewrite {} failed: {}", rel, e));
+                }
+            }
+        }
+    }
+    if let Some(mut meta) = read_profile_assets_meta(&dest) {
+        let local_base = data_local_base();
+        meta.custom_app_path =
+            rebase_asset_path(meta.custom_app_path, &manifest.source_base, &local_base);
+        meta.custom_icon_path =
+            rebase_asset_path(meta.custom_icon_path, &manifest.source_base, &local_base);
+        write_profile_assets_meta(&label, &meta);
+    }
+    let config = match profile_store::load(&label) {
+        Some(mut cfg) => {
+            cfg.id = label.clone();
+            if let Err(e) = profile_store::save(&label, &cfg) {
+                warnings.push(e);
+            }
+            Some(cfg)
+        }
+        None => None,
+    };
+    if let Some(v) = manifest.engine_version.as_deref() {
+        if find_engine_binary_for_version(v).is_none() {
+            warnings.push(format!("engine version {} is not installed", v));
+        }
+    }
+    write_log(
+        "INFO",
+        &format!(
+            "profile_import path={} label={} original={} files={} warnings={}",
+            path,
+            label,
+            manifest.label,
+            manifest.files.len(),
+            warnings.len()
+        ),
+    );
+    Ok(ProfileImportResult {
+        renamed: label != manifest.label,
+        label,
+        original_label: manifest.label,
+        config,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_and_cache_filters() {
+        assert!(is_runtime_file(Path::new("SingletonLock")));
+        assert!(is_runtime_file(Path::new("crashes/report.dmp")));
+        assert!(is_runtime_file(Path::new("lockfile")));
+        assert!(is_runtime_file(Path::new("SingletonSocket.old")));
+        assert!(!is_runtime_file(Path::new("Default/Cookies")));
+        assert!(is_cache_path(Path::new("Default/Code Cache/js/index")));
+        assert!(is_cache_path(Path::new(
+            "Default/Service Worker/CacheStorage/x"
+        )));
+        assert!(!is_cache_path(Path::new(
+            "Default/Local Storage/leveldb/000003.log"
+        )));
+        assert_eq!(archive_name(Path::new("assets.json")), "assets.json");
+        assert_eq!(
+            archive_name(Path::new("Default/Cookies")),
+            "user-data/Default/Cookies"
+        );
+    }
+
+    #[test]
+    fn test_allocate_label_and_path_rewrites() {
+        let taken = ["CHE-1", "CHE-1-2"];
+        assert_eq!(allocate_label("CHE-9", |l| taken.contains(&l)), "CHE-9");
+        assert_eq!(allocate_label("CHE-1", |l| taken.contains(&l)), "CHE-1-3");
+        let prefs = r#"{"download":{"default_directory":"C:\\Users\\a\\profiles\\CHE-1\\dl"}}"#;
+        let out = rewrite_profile_dir_refs(prefs, r"C:\Users\a\profiles\CHE-1", r"D:\p\CHE-1-3");
+        assert_eq!(
+            out,
+            r#"{"download":{"default_directory":"D:\\p\\CHE-1-3\\dl"}}"#
+        );
+    }
+
+    #[test]
+    fn test_manifest_rejects_unsafe_paths() {
+        let mut m = ProfileManifest {
+            format_version: FORMAT_VERSION,
+            label: "CHE-1".into(),
+            exported_at: String::new(),
+            app_version: String::new(),
+            engine_version: None,
+            source_base: String::new(),
+            source_profile_dir: String::new(),
+            exclude_caches: true,
+            files: vec![ManifestEntry {
+                path: "user-data/../../etc/passwd".into(),
+                size: 0,
+                sha256: String::new(),
+            }],
+        };
+        assert!(validate_manifest(&m).is_err());
+        m.files[0].path = "user-data/Default/Cookies".into();
+        assert!(validate_manifest(&m).is_ok());
+        m.files[0].path = r"user-data\..\..\etc\passwd".into();
+        assert!(validate_manifest(&m).is_err());
+        m.files[0].path = r"C:\Windows\win.ini".into();
+        assert!(validate_manifest(&m).is_err());
+        for label in ["../../x", "/tmp/x", r"..\x", "C:x", ".hidden", "a\0b"] {
+            let bad = ProfileManifest {
+                label: label.into(),
+                ..m.clone()
+            };
+            assert!(validate_manifest(&bad).is_err(), "{}", label);
+        }
+        m.format_version = 99;
+        assert!(validate_manifest(&m).is_err());
+    }
+
+    // 反斜杠路径按 / 解压，运行期文件不落盘
+    #[test]
+    fn test_extract_normalizes_paths_and_skips_runtime_files() {
+        let mut buf = std::io::Cursor::new(Vec::new());
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        let opts = zip::write::FileOptions::default();
+        let mut files = Vec::new();
+        for (name, data) in [
+            (r"user-data\Default\Bookmarks", "{}"),
+            ("user-data/SingletonLock", "host-1"),
+            ("user-data/lockfile", ""),
+        ] {
+            zip.start_file(name, opts).unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+            files.push(ManifestEntry {
+                path: name.into(),
+                size: data.len() as u64,
+                sha256: sha256_hex(data.as_bytes()),
+            });
+        }
+        zip.finish().unwrap();
+        drop(zip);
+        let manifest = ProfileManifest {
+            format_version: FORMAT_VERSION,
+            label: "CHE-1".into(),
+            exported_at: String::new(),
+            app_version: String::new(),
+            engine_version: None,
+            source_base: String::new(),
+            source_profile_dir: String::new(),
+            exclude_caches: true,
+            files,
+        };
+        validate_manifest(&manifest).unwrap();
+        let dest = std::env::temp_dir().join(format!(
+            "libre-archive-test-{}",
+            Local::now().format("%Y%m%d%H%M%S%f")
+        ));
+        let mut archive = zip::ZipArchive::new(buf).unwrap();
+        extract_verified(&mut archive, &manifest, &dest).unwrap();
+        assert_eq!(
+            fs::read_to_string(dest.join("Default").join("Bookmarks")).unwrap(),
+            "{}"
+        );
+        assert!(!dest.join("SingletonLock").exists());
+        assert!(!dest.join("lockfile").exists());
+        let _ = fs::remove_dir_all(&dest);
+    }
+}
@@ -0,0 +1,348 @@
+// profile 克隆：沿用配置，可选复制书签与扩展，不复制 cookie、历史与缓存，并重新生成指纹身份字段
+use crate::fingerprint::{self, Rng};
+use crate::profile_archive::{allocate_label, rewrite_profile_dir_refs};
+use crate::profile_store::{self, ProfileConfig};
+use crate::{
+    browser_running, generate_browser_icon, icon_index_for_label, profile_dir_path, vault,
+    write_log, write_profile_assets_meta, ProfileAssetsMeta,
+};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::Path;
+
+const BOOKMARK_FILES: &[&str] = &["Default/Bookmarks", "Default/Bookmarks.bak"];
+
+// 扩展本体与扩展数据；注册信息另从偏好中单独提取
+const EXTENSION_PATHS: &[&str] = &[
+    "Default/Extensions",
+    "Default/Local Extension Settings",
+    "Default/Sync Extension Settings",
+    "Default/Managed Extension Settings",
+    "Default/Extension State",
+    "Default/Extension Rules",
+    "Default/Extension Scripts",
+];
+
+// 扩展注册信息所在的偏好文件；新版 Chromium 多保存在 Secure Preferences
+const PREFERENCE_FILES: &[&str] = &["Default/Preferences", "Default/Secure Preferences"];
+// 解压加载的扩展，本体不在 Extensions 目录下
+const LOCATION_UNPACKED: u64 = 4;
+
+// 只记录历史行为的字段，克隆时去掉
+const HISTORY_FIELDS: &[&str] = &["lastOpenedAt"];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ProfileCloneOptions {
+    pub name: Option<String>,
+    pub include_bookmarks: bool,
+    pub include_extensions: bool,
+    // 指定时指纹重新生成的结果可复现
+    pub seed: Option<u64>,
+}
+
+impl Default for ProfileCloneOptions {
+    fn default() -> Self {
+        Self {
+            name: None,
+            include_bookmarks: true,
+            include_extensions: true,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileCloneResult {
+    pub label: String,
+    pub config: ProfileConfig,
+    pub icon_path: Option<String>,
+    pub copied_files: usize,
+}
+
+// 与前端 generateProfileId 相同的格式：CHE-<毫秒 base36>-<三位随机数>
+fn new_label(rng: &mut Rng) -> String {
+    let mut ms = Local::now().timestamp_millis().max(0) as u64;
+    let mut digits = Vec::new();
+    loop {
+        digits.push(b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ"[(ms % 36) as usize]);
+        ms /= 36;
+        if ms == 0 {
+            break;
+        }
+    }
+    digits.reverse();
+    let base = format!(
+        "CHE-{}-{:03}",
+        String::from_utf8_lossy(&digits),
+        rng.below(1000)
+    );
+    allocate_label(&base, |l| profile_dir_path(l).exists())
+}
+
+fn copy_tree(src: &Path, dest: &Path) -> Result<usize, String> {
+    if src.is_file() {
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("mkdir failed: {e}"))?;
+        }
+        fs::copy(src, dest).map_err(|e| format!("copy {} failed: {e}", src.display()))?;
+        return Ok(1);
+    }
+    let mut count = 0;
+    let Ok(entries) = fs::read_dir(src) else {
+        return Ok(0);
+    };
+    for entry in entries.flatten() {
+        count += copy_tree(&entry.path(), &dest.join(entry.file_name()))?;
+    }
+    Ok(count)
+}
+
+// 源 profile 的可复制路径（相对 user-data-dir）
+fn selected_paths(opts: &ProfileCloneOptions) -> Vec<&'static str> {
+    let mut paths = Vec::new();
+    if opts.include_bookmarks {
+        paths.extend_from_slice(BOOKMARK_FILES);
+    }
+    if opts.include_extensions {
+        paths.extend_from_slice(EXTENSION_PATHS);
+    }
+    paths
+}
+
+// 只取已复制扩展的 extensions.settings 条目；偏好中的账号、站点权限与完整性校验值都不复制
+fn extension_settings(source_dir: &Path, old_dir: &str, new_dir: &str) -> Map<String, Value> {
+    let mut out = Map::new();
+    for rel in PREFERENCE_FILES {
+        let Ok(content) = fs::read_to_string(source_dir.join(rel)) else {
+            continue;
+        };
+        let content = rewrite_profile_dir_refs(&content, old_dir, new_dir);
+        let Ok(prefs) = serde_json::from_str::<Value>(&content) else {
+            continue;
+        };
+        let Some(settings) = prefs
+            .pointer("/extensions/settings")
+            .and_then(Value::as_object)
+        else {
+            continue;
+        };
+        for (id, entry) in settings {
+            let copied = source_dir.join("Default/Extensions").join(id).is_dir();
+            if copied || entry["location"].as_u64() == Some(LOCATION_UNPACKED) {
+                out.insert(id.clone(), entry.clone());
+            }
+        }
+    }
+    out
+}
+
+fn clone_config(source: &ProfileConfig, label: &str, name: &str, rng: &mut Rng) -> ProfileConfig {
+    let mut cfg = source.clone();
+    cfg.id = label.to_string();
+    cfg.name = name.to_string();
+    cfg.window_title = Some(format!("{} - Libre Browser", name));
+    for key in HISTORY_FIELDS {
+        cfg.extra.remove(*key);
+    }
+    cfg.fingerprint = source.fingerprint.as_deref().map(|raw| {
+        match serde_json::from_str::<serde_json::Value>(raw) {
+            Ok(mut fp) => {
+                fingerprint::rerandomize_identity(&mut fp, rng);
+                fp.to_string()
+            }
+            Err(_) => raw.to_string(),
+        }
+    });
+    cfg
+}
+
+#[tauri::command]
+pub(crate) fn profile_clone(
+    label: &str,
+    opts: Option<ProfileCloneOptions>,
+) -> Result<ProfileCloneResult, String> {
+    let opts = opts.unwrap_or_default();
+    // 运行中的扩展数据库与偏好可能处于半写入状态
+    if browser_running(label).is_some() {
+        return Err(format!(
+            "profile {} is running, close it before cloning",
+            label
+        ));
+    }
+    let source = profile_store::load(label)
+        .ok_or_else(|| format!("profile config for {} not found", label))?;
+    let paths = selected_paths(&opts);
+    // 加密保存且未解密的 profile 无法读取书签与扩展
+    let source_dir = vault::user_data_dir(label);
+    if !paths.is_empty() && vault::is_sealed(label) && source_dir == profile_dir_path(label) {
+        return Err(format!(
+            "profile {} is sealed in the vault, open it or clone without bookmarks and extensions",
+            label
+        ));
+    }
+
+    let mut rng = opts.seed.map(Rng::new).unwrap_or_else(Rng::from_entropy);
+    let new_label = new_label(&mut rng);
+    let name = opts
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{} copy", source.name));
+    let dest_dir = profile_dir_path(&new_label);
+    fs::create_dir_all(&dest_dir).map_err(|e| format!("create profile dir failed: {e}"))?;
+
+    let mut copied_files = 0;
+    for rel in &paths {
+        match copy_tree(&source_dir.join(rel), &dest_dir.join(rel)) {
+            Ok(n) => copied_files += n,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&dest_dir);
+                return Err(e);
+            }
+        }
+    }
+    if opts.include_extensions {
+        let old_dir = source_dir.to_string_lossy().to_string();
+        let new_dir = dest_dir.to_string_lossy().to_string();
+        let settings = extension_settings(&source_dir, &old_dir, &new_dir);
+        if !settings.is_empty() {
+            // 新 profile 的 Preferences 只含扩展注册信息，其余由 Chromium 首次启动时生成
+            let prefs = json!({ "extensions": { "settings": settings } });
+            let written = fs::create_dir_all(dest_dir.join("Default"))
+                .and_then(|_| fs::write(dest_dir.join("Default/Preferences"), prefs.to_string()));
+            if let Err(e) = written {
+                let _ = fs::remove_dir_all(&dest_dir);
+                return Err(format!("write extension settings failed: {e}"));
+            }
+        }
+    }
+
+    let config = clone_config(&source, &new_label, &name, &mut rng);
+    if let Err(e) = profile_store::save(&new_label, &config) {
+        let _ = fs::remove_dir_all(&dest_dir);
+        return Err(e);
+    }
+    let icon_path = match generate_browser_icon(&name, icon_index_for_label(&new_label)) {
+        Ok(p) => {
+            let icon = p.to_string_lossy().to_string();
+            write_profile_assets_meta(
+                &new_label,
+                &ProfileAssetsMeta {
+                    custom_app_path: None,
+                    custom_icon_path: Some(icon.clone()),
+                },
+            );
+            Some(icon)
+        }
+        Err(e) => {
+            write_log(
+                "WARN",
+                &format!("profile_clone icon failed label={} err={}", new_label, e),
+            );
+            None
+        }
+    };
+    write_log(
+        "INFO",
+        &format!(
+            "profile_clone source={} label={} files={}",
+            label, new_label, copied_files
+        ),
+    );
+    Ok(ProfileCloneResult {
+        label: new_label,
+        config,
+        icon_path,
+        copied_files,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clone_config_rerandomizes_identity() {
+        let source = ProfileConfig {
+            id: "CHE-1".into(),
+            name: "shop".into(),
+            proxy: Some("socks5://127.0.0.1:1080".into()),
+            fingerprint: Some(
+                r#"{"userAgent":"ua","canvasFingerprint":"abc","webglRenderer":"AMD Radeon RX 580"}"#
+                    .into(),
+            ),
+            extra: serde_json::from_str(r#"{"lastOpenedAt":"2024-01-01","tags":["a"]}"#).unwrap(),
+            ..Default::default()
+        };
+        let cfg = clone_config(&source, "CHE-2", "shop copy", &mut Rng::new(3));
+        assert_eq!(cfg.id, "CHE-2");
+        assert_eq!(cfg.proxy, source.proxy);
+        assert_eq!(
+            cfg.window_title.as_deref(),
+            Some("shop copy - Libre Browser")
+        );
+        assert!(cfg.extra.get("lastOpenedAt").is_none());
+        assert!(cfg.extra.get("tags").is_some());
+        let fp: serde_json::Value =
+            serde_json::from_str(cfg.fingerprint.as_deref().unwrap()).unwrap();
+        assert_eq!(fp["userAgent"], "ua");
+        assert_ne!(fp["canvasFingerprint"], "abc");
+        assert_ne!(fp["webglRenderer"], "AMD Radeon RX 580");
+        assert!(fp["webglRenderer"]
+            .as_str()
+            .unwrap()
+            .starts_with("AMD Radeon"));
+    }
+
+    #[test]
+    fn test_selected_paths_exclude_cookies_and_history() {
+        let all = selected_paths(&ProfileCloneOptions::default());
+        assert!(all.contains(&"Default/Bookmarks"));
+        assert!(!all
+            .iter()
+            .any(|p| p.contains("Cookies") || p.contains("History")));
+        let none = selected_paths(&ProfileCloneOptions {
+            name: None,
+            include_bookmarks: false,
+            include_extensions: false,
+            seed: None,
+        });
+        assert!(none.is_empty());
+        assert!(!all.iter().any(|p| p.contains("Preferences")));
+    }
+
+    #[test]
+    fn test_extension_settings_keep_copied_entries_only() {
+        let src = std::env::temp_dir().join(format!("clone-t1-{}", Rng::from_entropy().hex(8)));
+        for id in ["aaa", "ddd"] {
+            fs::create_dir_all(src.join("Default/Extensions").join(id)).unwrap();
+        }
+        let old = src.to_string_lossy().to_string();
+        let prefs = json!({
+            "profile": { "name": "shop" },
+            "extensions": { "settings": {
+                "aaa": { "location": 1, "path": "aaa/1.0" },
+                "bbb": { "location": 1, "path": "bbb/1.0" },
+                "ccc": { "location": 4, "path": format!("{}/unpacked", old) },
+            } },
+        });
+        let secure = json!({
+            "extensions": { "settings": { "ddd": { "location": 1 } } },
+            "protection": { "macs": { "extensions": { "settings": { "ddd": "00" } } } },
+        });
+        fs::write(src.join("Default/Preferences"), prefs.to_string()).unwrap();
+        fs::write(src.join("Default/Secure Preferences"), secure.to_string()).unwrap();
+
+        let settings = extension_settings(&src, &old, "/new/dir");
+        let mut ids: Vec<&str> = settings.keys().map(String::as_str).collect();
+        ids.sort();
+        assert_eq!(ids, ["aaa", "ccc", "ddd"]);
+        assert_eq!(settings["ccc"]["path"], "/new/dir/unpacked");
+        let _ = fs::remove_dir_all(&src);
+    }
+}
@@ -0,0 +1,104 @@
+// 后端保存的 profile 配置，前端创建/编辑后同步到 profile 目录下的 profile.json
+use crate::dns::DnsConfig;
+use crate::fingerprint_lint::{self, FingerprintIssue};
+use crate::policy::ProfilePolicies;
+use crate::request_rules::{self, RequestRules};
+use crate::throttle::{self, ThrottleConfig};
+use crate::{profile_dir_path, supervisor::RestartMode};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+// 字段与前端 BrowserProfile 保持一致（camelCase），未识别的字段原样保留
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ProfileConfig {
+    pub id: String,
+    pub name: String,
+    pub project: String,
+    pub engine_version: Option<String>,
+    pub window_title: Option<String>,
+    pub proxy: Option<String>,
+    // 前端以 JSON 字符串保存指纹
+    pub fingerprint: Option<String>,
+    pub disable_cors: bool,
+    pub restart_policy: Option<RestartMode>,
+    pub remote_debugging: bool,
+    // 生成 Chromium 企业策略的设置
+    pub policies: ProfilePolicies,
+    // DNS 解析方式
+    pub dns: DnsConfig,
+    // 每日流量配额（MB），经本地中转时生效
+    pub traffic_quota_mb: Option<u64>,
+    // 网络限速与离线模拟
+    pub throttle: ThrottleConfig,
+    // 屏蔽、重定向与请求头改写规则
+    pub request_rules: RequestRules,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+pub(crate) const PROFILE_CONFIG_FILE: &str = "profile.json";
+
+fn profile_config_path(label: &str) -> PathBuf {
+    profile_dir_path(label).join(PROFILE_CONFIG_FILE)
+}
+
+pub(crate) fn load(label: &str) -> Option<ProfileConfig> {
+    let content = fs::read_to_string(profile_config_path(label)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+pub(crate) fn save(label: &str, config: &ProfileConfig) -> Result<(), String> {
+    let dir = profile_dir_path(label);
+    fs::create_dir_all(&dir).map_err(|e| format!("create profile dir failed: {e}"))?;
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("serialize profile config failed: {e}"))?;
+    fs::write(profile_config_path(label), content)
+        .map_err(|e| format!("write profile config failed: {e}"))
+}
+
+#[tauri::command]
+pub(crate) fn profile_config_get(label: &str) -> Option<ProfileConfig> {
+    load(label)
+}
+
+// 保存后返回指纹检查结果，由前端决定如何提示
+#[tauri::command]
+pub(crate) fn profile_config_save(
+    label: &str,
+    mut config: ProfileConfig,
+) -> Result<Vec<FingerprintIssue>, String> {
+    config.id = label.to_string();
+    request_rules::compile(&config.request_rules)
+        .map_err(|e| format!("request rules invalid: {}", e))?;
+    // 指纹中的网络信息跟随限速设置
+    if let Some(fp) = config
+        .fingerprint
+        .as_deref()
+        .and_then(|raw| throttle::align_fingerprint(raw, &config.throttle))
+    {
+        config.fingerprint = Some(fp);
+    }
+    save(label, &config)?;
+    Ok(config
+        .fingerprint
+        .as_deref()
+        .map(|raw| fingerprint_lint::validate_saved(raw, config.engine_version.as_deref(), None))
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_config_keeps_unknown_fields() {
+        let raw = r#"{"id":"CHE-1","name":"a","disableCors":true,"restartPolicy":"on_crash","lastOpenedAt":"2024-01-01"}"#;
+        let cfg: ProfileConfig = serde_json::from_str(raw).unwrap();
+        assert!(cfg.disable_cors);
+        assert_eq!(cfg.restart_policy, Some(RestartMode::OnCrash));
+        let back = serde_json::to_value(&cfg).unwrap();
+        assert_eq!(back["lastOpenedAt"], "2024-01-01");
+    }
+}
@@ -0,0 +1,296 @@
+// 代理可用性检查：经代理请求出口 IP 回显服务，记录延迟与出口 IP
+use crate::profile_archive::sha256_hex;
+use crate::proxies::{self, ProxyRef, ResolvedProxy};
+use crate::proxy_session;
+use crate::{app_settings, data_local_base, geoip, relay, write_log};
+use chrono::Local;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_CONCURRENCY: usize = 8;
+
+// 非代理库中的地址，结果按地址哈希保存
+static URL_RESULTS: OnceCell<Mutex<HashMap<String, ProxyCheck>>> = OnceCell::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct ProxyCheck {
+    pub ok: bool,
+    pub latency_ms: Option<u64>,
+    pub exit_ip: Option<String>,
+    pub error: Option<String>,
+    pub checked_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyCheckReport {
+    // 请求中的代理 ID 或地址
+    pub target: String,
+    pub result: ProxyCheck,
+}
+
+fn url_results_path() -> PathBuf {
+    data_local_base().join("proxy_checks.json")
+}
+
+fn url_results() -> &'static Mutex<HashMap<String, ProxyCheck>> {
+    URL_RESULTS.get_or_init(|| {
+        Mutex::new(
+            fs::read_to_string(url_results_path())
+                .ok()
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+        )
+    })
+}
+
+// 地址可能带账号密码，只保存哈希
+fn url_key(url: &str) -> String {
+    sha256_hex(url.trim().as_bytes())
+}
+
+pub(crate) fn check_url(proxy_url: &str, echo_url: &str) -> ProxyCheck {
+    let checked_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let started = Instant::now();
+    let result = reqwest::Proxy::all(proxy_url.trim())
+        .map_err(|e| format!("invalid proxy: {e}"))
+        .and_then(|proxy| {
+            reqwest::blocking::Client::builder()
+                .proxy(proxy)
+                .timeout(CHECK_TIMEOUT)
+                .build()
+                .map_err(|e| format!("build http client failed: {e}"))
+        })
+        .and_then(|client| {
+            client
+                .get(echo_url)
+                .send()
+                .and_then(|r| r.error_for_status())
+                .and_then(|r| r.text())
+                .map_err(|e| format!("request through proxy failed: {e}"))
+        })
+        .and_then(|body| {
+            geoip::parse_echo_body(&body)
+                .ok_or_else(|| format!("unexpected ip echo response: {}", body.trim()))
+        });
+    match result {
+        Ok(ip) => ProxyCheck {
+            ok: true,
+            latency_ms: Some(started.elapsed().as_millis() as u64),
+            exit_ip: Some(ip.to_string()),
+            error: None,
+            checked_at,
+        },
+        Err(e) => ProxyCheck {
+            ok: false,
+            latency_ms: None,
+            exit_ip: None,
+            error: Some(e),
+            checked_at,
+        },
+    }
+}
+
+// 代理库中的条目返回 (ID, 地址, 前置跳板)，其他按地址处理；代理池需逐个检查其中的代理
+type Target = (Option<String>, String, Vec<String>);
+
+fn resolve_target(target: &str) -> Result<Target, String> {
+    let target = target.trim();
+    let id = match ProxyRef::parse(target) {
+        Some(ProxyRef::Proxy(id)) => Some(id),
+        Some(ProxyRef::Pool(_)) => return Err("check the proxies of a pool individually".into()),
+        None if !target.contains("://") => Some(target.to_string()),
+        None => None,
+    };
+    match id {
+        Some(id) => proxies::snapshot()
+            .proxies
+            .into_iter()
+            .find(|p| p.id == id)
+            .ok_or_else(|| format!("proxy {} not found", id))
+            .and_then(|p| {
+                // 使用当前会话，不触发轮换
+                let url_of = |e: &proxies::ProxyEntry| {
+                    e.url_for(
+                        "proxy-check",
+                        &proxy_session::current_seed(&e.id, "proxy-check"),
+                    )
+                };
+                let chain = proxies::chain_urls(&p, url_of)?;
+                Ok((Some(id.clone()), url_of(&p)?, chain))
+            }),
+        None => Ok((None, target.to_string(), Vec::new())),
+    }
+}
+
+fn store(id: Option<&str>, url: &str, result: &ProxyCheck) {
+    if let Some(id) = id {
+        let saved = proxies::update(|inv| {
+            if let Some(p) = inv.proxies.iter_mut().find(|p| p.id == id) {
+                p.last_check = Some(result.clone());
+            }
+            Ok(())
+        });
+        if let Err(e) = saved {
+            write_log(
+                "WARN",
+                &format!("save proxy check failed id={} err={}", id, e),
+            );
+        }
+        return;
+    }
+    if let Ok(mut m) = url_results().lock() {
+        m.insert(url_key(url), result.clone());
+        if let Ok(content) = serde_json::to_string_pretty(&*m) {
+            let _ = fs::write(url_results_path(), content);
+        }
+    }
+}
+
+fn failed(error: String) -> ProxyCheck {
+    ProxyCheck {
+        error: Some(error),
+        checked_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        ..Default::default()
+    }
+}
+
+pub(crate) fn check_target(target: &str) -> ProxyCheck {
+    let (id, url, chain) = match resolve_target(target) {
+        Ok(v) => v,
+        Err(e) => return failed(e),
+    };
+    let echo = app_settings::current().ip_echo_url;
+    // 多跳代理经临时中转检查整条链路
+    let result = if chain.is_empty() {
+        check_url(&url, &echo)
+    } else {
+        relay::probe(&chain, &url, |u| check_url(u, &echo)).unwrap_or_else(failed)
+    };
+    store(id.as_deref(), &url, &result);
+    write_log(
+        if result.ok { "INFO" } else { "WARN" },
+        &format!(
+            "proxy_check id={:?} ok={} latency_ms={:?} exit_ip={:?} err={:?}",
+            id, result.ok, result.latency_ms, result.exit_ip, result.error
+        ),
+    );
+    result
+}
+
+// 开启“启动前检查代理”时调用，检查失败则拒绝启动
+pub(crate) fn ensure_alive(label: &str, proxy: Option<&ResolvedProxy>) -> Result<(), String> {
+    let Some(proxy) = proxy.filter(|p| !p.url.trim().is_empty()) else {
+        return Ok(());
+    };
+    let echo = app_settings::current().ip_echo_url;
+    let result = if proxy.chain.is_empty() {
+        let result = check_url(&proxy.url, &echo);
+        store(None, &proxy.url, &result);
+        result
+    } else {
+        relay::probe(&proxy.chain, &proxy.url, |u| check_url(u, &echo)).unwrap_or_else(failed)
+    };
+    if result.ok {
+        return Ok(());
+    }
+    Err(format!(
+        "proxy check failed for {}: {}",
+        label,
+        result.error.unwrap_or_default()
+    ))
+}
+
+#[tauri::command]
+pub(crate) async fn proxy_check(target: String) -> Result<ProxyCheck, String> {
+    tauri::async_runtime::spawn_blocking(move || check_target(&target))
+        .await
+        .map_err(|e| format!("proxy_check task failed: {e}"))
+}
+
+#[tauri::command]
+pub(crate) async fn proxy_check_bulk(
+    targets: Vec<String>,
+    concurrency: Option<usize>,
+) -> Result<Vec<ProxyCheckReport>, String> {
+    let workers = concurrency.unwrap_or(DEFAULT_CONCURRENCY).clamp(1, 32);
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut out = Vec::with_capacity(targets.len());
+        for chunk in targets.chunks(workers) {
+            let results: Vec<ProxyCheck> = std::thread::scope(|s| {
+                let handles: Vec<_> = chunk
+                    .iter()
+                    .map(|t| s.spawn(move || check_target(t)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|h| h.join().unwrap_or_default())
+                    .collect()
+            });
+            out.extend(
+                chunk
+                    .iter()
+                    .cloned()
+                    .zip(results)
+                    .map(|(target, result)| ProxyCheckReport { target, result }),
+            );
+        }
+        out
+    })
+    .await
+    .map_err(|e| format!("proxy_check_bulk task failed: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    // 本地桩代理：对任意请求返回固定出口 IP
+    fn stub_proxy(body: &'static str) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buf = [0u8; 2048];
+                let _ = stream.read(&mut buf);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn test_check_through_stub_proxy() {
+        let port = stub_proxy("203.0.113.9\n");
+        let result = check_url(
+            &format!("http://127.0.0.1:{}", port),
+            "http://echo.invalid/",
+        );
+        assert!(result.ok, "{:?}", result.error);
+        assert_eq!(result.exit_ip.as_deref(), Some("203.0.113.9"));
+        assert!(result.latency_ms.is_some());
+
+        let dead = TcpListener::bind("127.0.0.1:0").unwrap();
+        let dead_port = dead.local_addr().unwrap().port();
+        drop(dead);
+        let result = check_url(
+            &format!("http://127.0.0.1:{}", dead_port),
+            "http://echo.invalid/",
+        );
+        assert!(!result.ok);
+        assert!(result.error.is_some());
+    }
+}
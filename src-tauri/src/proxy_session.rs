@@ -0,0 +1,320 @@
+// 代理凭据占位符：{label}、{session}、{rand:N}、{date}、{country}，启动时展开
+// 会话种子按 (代理, profile) 保存在 data_local_base()/proxy_sessions.json，按轮换策略更新
+use crate::fingerprint::Rng;
+use crate::profile_archive::sha256_hex;
+use crate::proxies::ProxyEntry;
+use crate::{data_local_base, write_log};
+use chrono::Local;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+const MAX_RAND_LEN: usize = 32;
+const DEFAULT_RAND_LEN: usize = 8;
+const RAND_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+static SESSIONS: OnceCell<Mutex<BTreeMap<String, SessionState>>> = OnceCell::new();
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionRotation {
+    // 每次启动生成新会话
+    PerLaunch,
+    // 每天第一次启动时生成新会话
+    PerDay,
+    // 只在手动轮换时更新
+    #[default]
+    Manual,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct SessionState {
+    pub seed: String,
+    pub minted_at: String,
+    // %Y%m%d，per_day 策略按此判断
+    pub day: String,
+}
+
+pub(crate) struct ExpandContext<'a> {
+    pub label: &'a str,
+    pub seed: &'a str,
+    pub date: &'a str,
+    pub country: Option<&'a str>,
+}
+
+fn sessions_path() -> PathBuf {
+    data_local_base().join("proxy_sessions.json")
+}
+
+fn sessions() -> &'static Mutex<BTreeMap<String, SessionState>> {
+    SESSIONS.get_or_init(|| {
+        Mutex::new(
+            fs::read_to_string(sessions_path())
+                .ok()
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+        )
+    })
+}
+
+fn persist(map: &BTreeMap<String, SessionState>) {
+    let result = serde_json::to_string_pretty(map)
+        .map_err(|e| e.to_string())
+        .and_then(|content| fs::write(sessions_path(), content).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        write_log("WARN", &format!("save proxy sessions failed: {}", e));
+    }
+}
+
+fn key(proxy_id: &str, label: &str) -> String {
+    format!("{}/{}", proxy_id, label)
+}
+
+fn today() -> String {
+    Local::now().format("%Y%m%d").to_string()
+}
+
+// 未生成过会话时按 profile 固定，与旧版 {session} 行为一致
+fn default_seed(label: &str) -> String {
+    sha256_hex(label.as_bytes())[..10].to_string()
+}
+
+fn mint(day: &str) -> SessionState {
+    SessionState {
+        seed: Rng::from_entropy().hex(10),
+        minted_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        day: day.to_string(),
+    }
+}
+
+pub(crate) fn needs_rotation(
+    rotation: SessionRotation,
+    state: Option<&SessionState>,
+    today: &str,
+) -> bool {
+    match (rotation, state) {
+        (SessionRotation::PerLaunch, _) => true,
+        (SessionRotation::PerDay, Some(s)) => s.day != today,
+        (SessionRotation::PerDay, None) => true,
+        (SessionRotation::Manual, _) => false,
+    }
+}
+
+// 启动时调用：按轮换策略决定是否生成新会话
+pub(crate) fn seed_for_launch(entry: &ProxyEntry, label: &str) -> String {
+    let day = today();
+    let Ok(mut m) = sessions().lock() else {
+        return default_seed(label);
+    };
+    let k = key(&entry.id, label);
+    if needs_rotation(entry.rotation, m.get(&k), &day) {
+        let state = mint(&day);
+        m.insert(k, state.clone());
+        persist(&m);
+        return state.seed;
+    }
+    m.get(&k)
+        .map(|s| s.seed.clone())
+        .unwrap_or_else(|| default_seed(label))
+}
+
+// 检查代理等场景使用当前会话，不触发轮换
+pub(crate) fn current_seed(proxy_id: &str, label: &str) -> String {
+    sessions()
+        .lock()
+        .ok()
+        .and_then(|m| m.get(&key(proxy_id, label)).map(|s| s.seed.clone()))
+        .unwrap_or_else(|| default_seed(label))
+}
+
+// {rand:N} 由会话种子派生，同一会话内展开结果不变
+fn rand_chars(seed: &str, index: usize, len: usize) -> String {
+    let digest = sha256_hex(format!("{}#{}", seed, index).as_bytes());
+    let mut rng = Rng::new(u64::from_str_radix(&digest[..16], 16).unwrap_or(0));
+    (0..len)
+        .map(|_| RAND_CHARS[rng.below(RAND_CHARS.len())] as char)
+        .collect()
+}
+
+// 服务商常用 - 分隔参数，label 中只保留字母数字
+fn label_token(label: &str) -> String {
+    label
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect()
+}
+
+fn rand_len(arg: Option<&str>) -> Result<usize, String> {
+    let len = match arg {
+        Some(n) => n
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid {{rand:N}} length: {}", n))?,
+        None => DEFAULT_RAND_LEN,
+    };
+    if len == 0 || len > MAX_RAND_LEN {
+        return Err(format!("{{rand:N}} length must be 1-{}", MAX_RAND_LEN));
+    }
+    Ok(len)
+}
+
+pub(crate) fn expand(template: &str, ctx: &ExpandContext) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    let mut rand_index = 0;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("unclosed placeholder in {}", template))?;
+        let (name, arg) = match after[..end].split_once(':') {
+            Some((n, a)) => (n, Some(a)),
+            None => (&after[..end], None),
+        };
+        match (name, arg) {
+            ("label", None) => out.push_str(&label_token(ctx.label)),
+            ("session", None) => out.push_str(ctx.seed),
+            ("date", None) => out.push_str(ctx.date),
+            ("country", None) => {
+                let country = ctx
+                    .country
+                    .map(str::trim)
+                    .filter(|c| !c.is_empty())
+                    .ok_or("{country} requires the proxy country to be set")?;
+                out.push_str(&country.to_ascii_lowercase());
+            }
+            ("rand", arg) => {
+                out.push_str(&rand_chars(ctx.seed, rand_index, rand_len(arg)?));
+                rand_index += 1;
+            }
+            _ => return Err(format!("unknown placeholder {{{}}}", &after[..end])),
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+// 保存代理时校验模板，避免到启动时才报错
+pub(crate) fn validate_template(template: &str, country: Option<&str>) -> Result<(), String> {
+    let ctx = ExpandContext {
+        label: "check",
+        seed: "0000000000",
+        date: "20000101",
+        country,
+    };
+    expand(template, &ctx).map(|_| ())
+}
+
+// 手动轮换；未指定 profile 时轮换该代理下所有已有会话
+#[tauri::command]
+pub(crate) fn proxy_session_rotate(proxy_id: &str, label: Option<String>) -> Result<usize, String> {
+    let day = today();
+    let mut m = sessions()
+        .lock()
+        .map_err(|_| "proxy session lock poisoned".to_string())?;
+    let keys: Vec<String> = match label.as_deref().map(str::trim).filter(|l| !l.is_empty()) {
+        Some(l) => vec![key(proxy_id, l)],
+        None => {
+            let prefix = format!("{}/", proxy_id);
+            m.keys()
+                .filter(|k| k.starts_with(&prefix))
+                .cloned()
+                .collect()
+        }
+    };
+    for k in &keys {
+        m.insert(k.clone(), mint(&day));
+    }
+    persist(&m);
+    write_log(
+        "INFO",
+        &format!(
+            "proxy_session_rotate proxy={} label={:?} rotated={}",
+            proxy_id,
+            label,
+            keys.len()
+        ),
+    );
+    Ok(keys.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx<'a>(seed: &'a str, country: Option<&'a str>) -> ExpandContext<'a> {
+        ExpandContext {
+            label: "CHE-12",
+            seed,
+            date: "20260101",
+            country,
+        }
+    }
+
+    #[test]
+    fn test_expand_placeholders() {
+        let c = ctx("abc123", Some("US"));
+        let out = expand("user-{label}-session-{rand:8}-country-{country}-{date}", &c).unwrap();
+        let parts: Vec<&str> = out.split('-').collect();
+        assert_eq!(parts[1], "CHE12");
+        assert_eq!(parts[3].len(), 8);
+        assert!(parts[3].chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_eq!(&parts[5..], ["us", "20260101"]);
+        // 同一会话稳定，换会话后变化；同一模板中多个 {rand} 互不相同
+        assert_eq!(
+            out,
+            expand("user-{label}-session-{rand:8}-country-{country}-{date}", &c).unwrap()
+        );
+        assert_ne!(
+            expand("{rand}", &c).unwrap(),
+            expand("{rand}", &ctx("zzz", None)).unwrap()
+        );
+        let two = expand("{rand:6}{rand:6}", &c).unwrap();
+        assert_ne!(two[..6], two[6..]);
+        assert_eq!(expand("s-{session}", &c).unwrap(), "s-abc123");
+        assert_eq!(expand("plain", &c).unwrap(), "plain");
+
+        assert!(expand("{country}", &ctx("a", None)).is_err());
+        assert!(expand("{rand:0}", &c).is_err());
+        assert!(expand("{rand:99}", &c).is_err());
+        assert!(expand("{nope}", &c).is_err());
+        assert!(expand("user-{label", &c).is_err());
+    }
+
+    #[test]
+    fn test_rotation_policy() {
+        let state = SessionState {
+            seed: "s".into(),
+            minted_at: String::new(),
+            day: "20260101".into(),
+        };
+        assert!(needs_rotation(
+            SessionRotation::PerLaunch,
+            Some(&state),
+            "20260101"
+        ));
+        assert!(!needs_rotation(
+            SessionRotation::PerDay,
+            Some(&state),
+            "20260101"
+        ));
+        assert!(needs_rotation(
+            SessionRotation::PerDay,
+            Some(&state),
+            "20260102"
+        ));
+        assert!(needs_rotation(SessionRotation::PerDay, None, "20260101"));
+        assert!(!needs_rotation(SessionRotation::Manual, None, "20260101"));
+        assert!(!needs_rotation(
+            SessionRotation::Manual,
+            Some(&state),
+            "20260102"
+        ));
+    }
+}
@@ -0,0 +1,570 @@
+// 按 profile 的请求规则：屏蔽、重定向、增删请求头与覆盖 Accept-Language
+// 生成 declarativeNetRequest 扩展作用于所有请求；经本地中转时中转对明文 HTTP 同样执行，CONNECT 只能按域名屏蔽
+use crate::profile_store;
+use crate::write_log;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub(crate) const EXTENSION_DIR: &str = "LibreExtensions/request_rules";
+// 扩展 ID 由该公钥派生，见 policy::own_extension_ids
+pub(crate) const EXTENSION_KEY: &str = "MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAqw4Z7LqCskS8WlexGeAzAmaYZH5HWfe2FqKl99XDsYGfrR6L96ZcUTjMa41AjpxZEjk03J7LlWMzV6MbuJqY+0vlVnVWMXsc+mxaVpVEMf71x3vv94MkaRrDf9qQ+Rrp4AU86shVOGyLSaaQ9y3i4IuPgaXbfIVLYbo8R4XWoTVGXJUWhPsJ+KboKQb1E7LdJIGvXbHicKuTRY7wibP56C1Z6aQ37fl+PmcP2chpAyFNr7h4fNBLJmz6zJbkklnJLz2ZeqnD2Yjhv5cZxijUzg0dDYW1b6npEb8Bg2M78PeviENKKq+fqrdz66BwqGVWepemTOsaZO52UN5RR4RakQIDAQAB";
+const MAX_RULES: usize = 1000;
+const RESOURCE_TYPES: &[&str] = &[
+    "main_frame",
+    "sub_frame",
+    "stylesheet",
+    "script",
+    "image",
+    "font",
+    "object",
+    "xmlhttprequest",
+    "ping",
+    "csp_report",
+    "media",
+    "websocket",
+    "webtransport",
+    "webbundle",
+    "other",
+];
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    #[default]
+    Block,
+    Redirect,
+    SetHeader,
+    RemoveHeader,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RequestRule {
+    pub action: RuleAction,
+    // 域名（含子域名），或 declarativeNetRequest 的 urlFilter，如 ||ads.example^、*/track?*
+    pub pattern: String,
+    pub redirect_to: Option<String>,
+    pub header: Option<String>,
+    pub value: Option<String>,
+}
+
+// profile.json 中的 requestRules 字段
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RequestRules {
+    pub rules: Vec<RequestRule>,
+    pub accept_language: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Matcher {
+    Any,
+    Domain(String),
+    Url(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Action {
+    Block,
+    Redirect(String),
+    SetHeader(String, String),
+    RemoveHeader(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Compiled {
+    matcher: Matcher,
+    action: Action,
+}
+
+// 校验后的规则，供中转匹配与生成扩展
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct RuleSet {
+    rules: Vec<Compiled>,
+}
+
+// 中转对明文请求的处理结果
+#[derive(Debug, PartialEq)]
+pub(crate) enum Verdict {
+    Pass,
+    Block,
+    Redirect(String),
+}
+
+fn is_domain(pattern: &str) -> bool {
+    pattern
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+        && !pattern.starts_with('.')
+        && !pattern.ends_with('.')
+}
+
+fn matcher(pattern: &str) -> Result<Matcher, String> {
+    let p = pattern.trim();
+    if p.is_empty() {
+        return Err("rule pattern is empty".into());
+    }
+    if !p.is_ascii() || p.chars().any(|c| c.is_whitespace()) {
+        return Err(format!("rule pattern must be ascii without spaces: {}", p));
+    }
+    if is_domain(p) {
+        return Ok(Matcher::Domain(p.to_ascii_lowercase()));
+    }
+    if p.starts_with("||*") {
+        return Err(format!("rule pattern must not start with ||*: {}", p));
+    }
+    Ok(Matcher::Url(p.to_ascii_lowercase()))
+}
+
+fn header_name(rule: &RequestRule) -> Result<String, String> {
+    let name = rule
+        .header
+        .as_deref()
+        .map(str::trim)
+        .filter(|h| !h.is_empty())
+        .ok_or("header rule requires a header name")?;
+    let token = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+    if !name.chars().all(token) {
+        return Err(format!("invalid header name: {}", name));
+    }
+    Ok(name.to_string())
+}
+
+fn header_value(value: Option<&str>) -> Result<String, String> {
+    let value = value
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .ok_or("header rule requires a value")?;
+    if value.chars().any(|c| c.is_control()) {
+        return Err(format!(
+            "header value must not contain control characters: {:?}",
+            value
+        ));
+    }
+    Ok(value.to_string())
+}
+
+fn compile_rule(rule: &RequestRule) -> Result<Compiled, String> {
+    let matcher = matcher(&rule.pattern)?;
+    let action = match rule.action {
+        RuleAction::Block => Action::Block,
+        RuleAction::Redirect => {
+            let target = rule
+                .redirect_to
+                .as_deref()
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .ok_or("redirect rule requires a target url")?;
+            let url = url::Url::parse(target)
+                .map_err(|e| format!("invalid redirect url {}: {e}", target))?;
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(format!("redirect url must be http or https: {}", target));
+            }
+            // 目标仍命中本规则时会无限重定向
+            if matcher_hits(&matcher, url.host_str().unwrap_or_default(), url.as_str()) {
+                return Err(format!(
+                    "redirect url matches the rule's own pattern: {}",
+                    target
+                ));
+            }
+            Action::Redirect(url.to_string())
+        }
+        RuleAction::SetHeader => {
+            Action::SetHeader(header_name(rule)?, header_value(rule.value.as_deref())?)
+        }
+        RuleAction::RemoveHeader => Action::RemoveHeader(header_name(rule)?),
+    };
+    Ok(Compiled { matcher, action })
+}
+
+pub(crate) fn compile(config: &RequestRules) -> Result<RuleSet, String> {
+    if config.rules.len() > MAX_RULES {
+        return Err(format!("at most {} request rules are allowed", MAX_RULES));
+    }
+    let mut rules = config
+        .rules
+        .iter()
+        .enumerate()
+        .map(|(i, r)| compile_rule(r).map_err(|e| format!("rule {}: {}", i + 1, e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(lang) = config
+        .accept_language
+        .as_deref()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+    {
+        let ok = |c: char| c.is_ascii_alphanumeric() || "-,;=. *".contains(c);
+        if !lang.chars().all(ok) {
+            return Err(format!("invalid Accept-Language: {}", lang));
+        }
+        rules.push(Compiled {
+            matcher: Matcher::Any,
+            action: Action::SetHeader("Accept-Language".into(), lang.to_string()),
+        });
+    }
+    Ok(RuleSet { rules })
+}
+
+// declarativeNetRequest 规则；同一请求头只有优先级最高的修改生效，
+// 请求头规则按顺序递增优先级，与中转中后面的规则覆盖前面的一致，Accept-Language 排在最后
+pub(crate) fn dnr_rules(set: &RuleSet) -> Vec<Value> {
+    set.rules
+        .iter()
+        .enumerate()
+        .map(|(i, r)| {
+            let mut condition = json!({ "resourceTypes": RESOURCE_TYPES });
+            match &r.matcher {
+                Matcher::Any => {}
+                Matcher::Domain(d) => condition["requestDomains"] = json!([d]),
+                Matcher::Url(u) => condition["urlFilter"] = json!(u),
+            }
+            let action = match &r.action {
+                Action::Block => json!({ "type": "block" }),
+                Action::Redirect(url) => json!({ "type": "redirect", "redirect": { "url": url } }),
+                Action::SetHeader(h, v) => json!({
+                    "type": "modifyHeaders",
+                    "requestHeaders": [{ "header": h, "operation": "set", "value": v }],
+                }),
+                Action::RemoveHeader(h) => json!({
+                    "type": "modifyHeaders",
+                    "requestHeaders": [{ "header": h, "operation": "remove" }],
+                }),
+            };
+            let priority = match &r.action {
+                Action::SetHeader(..) | Action::RemoveHeader(_) => i + 1,
+                _ => 1,
+            };
+            json!({ "id": i + 1, "priority": priority, "action": action, "condition": condition })
+        })
+        .collect()
+}
+
+// urlFilter 的分隔符：字母数字与 _-.% 以外的字符，或字符串结尾
+fn is_separator(c: u8) -> bool {
+    !(c.is_ascii_alphanumeric() || b"_-.%".contains(&c))
+}
+
+fn match_here(p: &[u8], s: &[u8], end_anchor: bool) -> bool {
+    match p.first() {
+        None => !end_anchor || s.is_empty(),
+        Some(b'*') => (0..=s.len()).any(|i| match_here(&p[1..], &s[i..], end_anchor)),
+        Some(b'^') => match s.first() {
+            None => match_here(&p[1..], s, end_anchor),
+            Some(c) if is_separator(*c) => match_here(&p[1..], &s[1..], end_anchor),
+            _ => false,
+        },
+        Some(c) => s.first() == Some(c) && match_here(&p[1..], &s[1..], end_anchor),
+    }
+}
+
+// 支持 urlFilter 的 *、^、| 与 || 语法，不区分大小写
+fn url_matches(filter: &str, url: &str) -> bool {
+    let url = url.to_ascii_lowercase();
+    let (body, end_anchor) = match filter.strip_suffix('|') {
+        Some(b) => (b, true),
+        None => (filter, false),
+    };
+    let s = url.as_bytes();
+    if let Some(rest) = body.strip_prefix("||") {
+        // 从主机名开头或任一级子域名处开始匹配
+        let host_start = url.find("://").map(|i| i + 3).unwrap_or(0);
+        let host_end = url[host_start..]
+            .find(['/', '?', ':', '#'])
+            .map(|i| host_start + i)
+            .unwrap_or(url.len());
+        return std::iter::once(host_start)
+            .chain(
+                (host_start..host_end)
+                    .filter(|&i| s[i] == b'.')
+                    .map(|i| i + 1),
+            )
+            .any(|i| match_here(rest.as_bytes(), &s[i..], end_anchor));
+    }
+    if let Some(rest) = body.strip_prefix('|') {
+        return match_here(rest.as_bytes(), s, end_anchor);
+    }
+    (0..=s.len()).any(|i| match_here(body.as_bytes(), &s[i..], end_anchor))
+}
+
+fn domain_matches(domain: &str, host: &str) -> bool {
+    let host = host.to_ascii_lowercase();
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+fn matcher_hits(m: &Matcher, host: &str, url: &str) -> bool {
+    match m {
+        Matcher::Any => true,
+        Matcher::Domain(d) => domain_matches(d, host),
+        Matcher::Url(f) => url_matches(f, url),
+    }
+}
+
+impl RuleSet {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    // CONNECT 只知道目标主机，只按域名规则屏蔽
+    pub(crate) fn blocks_host(&self, host: &str) -> bool {
+        self.rules.iter().any(|r| {
+            r.action == Action::Block
+                && matches!(&r.matcher, Matcher::Domain(d) if domain_matches(d, host))
+        })
+    }
+
+    // 与 declarativeNetRequest 一致：屏蔽优先于重定向，请求头修改按顺序执行，同一请求头以后面的规则为准
+    pub(crate) fn apply(&self, host: &str, url: &str, headers: &mut Vec<String>) -> Verdict {
+        let hits: Vec<&Compiled> = self
+            .rules
+            .iter()
+            .filter(|r| matcher_hits(&r.matcher, host, url))
+            .collect();
+        if hits.iter().any(|r| r.action == Action::Block) {
+            return Verdict::Block;
+        }
+        if let Some(Action::Redirect(to)) = hits
+            .iter()
+            .map(|r| &r.action)
+            .find(|a| matches!(a, Action::Redirect(_)))
+        {
+            return Verdict::Redirect(to.clone());
+        }
+        for r in hits {
+            let name = match &r.action {
+                Action::SetHeader(h, _) | Action::RemoveHeader(h) => h,
+                _ => continue,
+            };
+            headers.retain(|line| {
+                !line
+                    .split(':')
+                    .next()
+                    .unwrap_or("")
+                    .trim()
+                    .eq_ignore_ascii_case(name)
+            });
+            if let Action::SetHeader(h, v) = &r.action {
+                headers.push(format!("{}: {}", h, v));
+            }
+        }
+        Verdict::Pass
+    }
+}
+
+// 中转使用；没有规则或规则无效时返回 None（无效规则在启动前已拒绝）
+pub(crate) fn rules_for(label: &str) -> Option<RuleSet> {
+    let config = profile_store::load(label)?.request_rules;
+    compile(&config).ok().filter(|s| !s.is_empty())
+}
+
+// 生成扩展，返回扩展目录
+pub(crate) fn write_extension(user_data_dir: &Path, set: &RuleSet) -> Result<PathBuf, String> {
+    let dir = user_data_dir.join(EXTENSION_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("create request rules dir failed: {e}"))?;
+    let manifest = json!({
+        "manifest_version": 3,
+        "name": "Libre Request Rules",
+        "version": "1.0",
+        "key": EXTENSION_KEY,
+        "permissions": ["declarativeNetRequest"],
+        "host_permissions": ["<all_urls>"],
+        "declarative_net_request": {
+            "rule_resources": [{ "id": "rules", "enabled": true, "path": "rules.json" }],
+        },
+    });
+    let content = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("serialize extension manifest failed: {e}"))?;
+    fs::write(dir.join("manifest.json"), content)
+        .map_err(|e| format!("write extension manifest failed: {e}"))?;
+    let rules = serde_json::to_string_pretty(&dnr_rules(set))
+        .map_err(|e| format!("serialize request rules failed: {e}"))?;
+    fs::write(dir.join("rules.json"), rules)
+        .map_err(|e| format!("write request rules failed: {e}"))?;
+    Ok(dir)
+}
+
+// 启动前调用：规则无效时拒绝启动，返回需追加的启动参数
+pub(crate) fn prepare_launch(label: &str, user_data_dir: &Path) -> Result<Vec<String>, String> {
+    let config = profile_store::load(label).unwrap_or_default().request_rules;
+    let set =
+        compile(&config).map_err(|e| format!("profile {} request rules invalid: {}", label, e))?;
+    if set.is_empty() {
+        let _ = fs::remove_dir_all(user_data_dir.join(EXTENSION_DIR));
+        return Ok(Vec::new());
+    }
+    let dir = write_extension(user_data_dir, &set)?;
+    write_log(
+        "INFO",
+        &format!("request rules label={} count={}", label, set.rules.len()),
+    );
+    Ok(vec![format!("--load-extension={}", dir.to_string_lossy())])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(action: RuleAction, pattern: &str) -> RequestRule {
+        RequestRule {
+            action,
+            pattern: pattern.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compile_to_dnr_rules() {
+        let config = RequestRules {
+            rules: vec![
+                rule(RuleAction::Block, "Ads.Example.com"),
+                RequestRule {
+                    redirect_to: Some("https://example.org/blank".into()),
+                    ..rule(RuleAction::Redirect, "||cdn.example^*/track?")
+                },
+                RequestRule {
+                    header: Some("X-Debug".into()),
+                    value: Some("1".into()),
+                    ..rule(RuleAction::SetHeader, "*")
+                },
+                RequestRule {
+                    header: Some("Referer".into()),
+                    ..rule(RuleAction::RemoveHeader, "example.net")
+                },
+            ],
+            accept_language: Some("de-DE,de;q=0.9".into()),
+        };
+        let rules = dnr_rules(&compile(&config).unwrap());
+        assert_eq!(rules.len(), 5);
+        assert_eq!(rules[0]["id"], 1);
+        assert_eq!(rules[0]["action"]["type"], "block");
+        assert_eq!(
+            rules[0]["condition"]["requestDomains"],
+            json!(["ads.example.com"])
+        );
+        assert_eq!(rules[1]["condition"]["urlFilter"], "||cdn.example^*/track?");
+        assert_eq!(
+            rules[1]["action"]["redirect"]["url"],
+            "https://example.org/blank"
+        );
+        assert_eq!(
+            rules[3]["action"]["requestHeaders"][0],
+            json!({ "header": "Referer", "operation": "remove" })
+        );
+        assert!(rules[4]["condition"].get("urlFilter").is_none());
+        assert_eq!(
+            rules[4]["action"]["requestHeaders"][0]["value"],
+            "de-DE,de;q=0.9"
+        );
+        assert!(rules[4]["condition"]["resourceTypes"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("main_frame")));
+        // 后面的请求头规则优先级更高，Accept-Language 最高
+        assert_eq!(rules[0]["priority"], 1);
+        assert!(rules[4]["priority"].as_u64() > rules[2]["priority"].as_u64());
+
+        for bad in [
+            rule(RuleAction::Block, " "),
+            rule(RuleAction::Block, "bad pattern"),
+            rule(RuleAction::Redirect, "example.com"),
+            RequestRule {
+                redirect_to: Some("javascript:alert(1)".into()),
+                ..rule(RuleAction::Redirect, "example.com")
+            },
+            RequestRule {
+                redirect_to: Some("https://www.example.com/landing".into()),
+                ..rule(RuleAction::Redirect, "example.com")
+            },
+            RequestRule {
+                redirect_to: Some("https://cdn.test/new/track".into()),
+                ..rule(RuleAction::Redirect, "*/track")
+            },
+            rule(RuleAction::SetHeader, "example.com"),
+            RequestRule {
+                header: Some("Bad Header".into()),
+                value: Some("x".into()),
+                ..rule(RuleAction::SetHeader, "example.com")
+            },
+            RequestRule {
+                header: Some("X-A".into()),
+                value: Some("a\r\nb".into()),
+                ..rule(RuleAction::SetHeader, "example.com")
+            },
+        ] {
+            let config = RequestRules {
+                rules: vec![bad.clone()],
+                ..Default::default()
+            };
+            assert!(compile(&config).is_err(), "{:?}", bad);
+        }
+        let lang = RequestRules {
+            accept_language: Some("en\r\nX: y".into()),
+            ..Default::default()
+        };
+        assert!(compile(&lang).is_err());
+    }
+
+    #[test]
+    fn test_relay_matching() {
+        let config = RequestRules {
+            rules: vec![
+                rule(RuleAction::Block, "ads.example.com"),
+                RequestRule {
+                    redirect_to: Some("http://example.org/".into()),
+                    ..rule(RuleAction::Redirect, "||example.com^*/old|")
+                },
+                RequestRule {
+                    header: Some("cookie".into()),
+                    ..rule(RuleAction::RemoveHeader, "*")
+                },
+                RequestRule {
+                    header: Some("Accept-Language".into()),
+                    value: Some("ja-JP".into()),
+                    ..rule(RuleAction::SetHeader, "*")
+                },
+            ],
+            accept_language: Some("fr-FR".into()),
+        };
+        let set = compile(&config).unwrap();
+        assert!(set.blocks_host("x.ads.example.com"));
+        assert!(!set.blocks_host("example.com"));
+
+        let mut headers = vec![];
+        assert_eq!(
+            set.apply("ads.example.com", "http://ads.example.com/a", &mut headers),
+            Verdict::Block
+        );
+        assert_eq!(
+            set.apply(
+                "www.example.com",
+                "http://www.example.com/p/old",
+                &mut headers
+            ),
+            Verdict::Redirect("http://example.org/".into())
+        );
+        assert_eq!(
+            set.apply(
+                "www.example.com",
+                "http://www.example.com/p/old2",
+                &mut headers
+            ),
+            Verdict::Pass
+        );
+        assert_eq!(
+            set.apply("notexample.com", "http://notexample.com/old", &mut headers),
+            Verdict::Pass
+        );
+
+        let mut headers = vec![
+            "Host: a.test".to_string(),
+            "Cookie: s=1".to_string(),
+            "accept-language: en-US".to_string(),
+        ];
+        assert_eq!(
+            set.apply("a.test", "http://a.test/", &mut headers),
+            Verdict::Pass
+        );
+        assert_eq!(headers, ["Host: a.test", "Accept-Language: fr-FR"]);
+    }
+}
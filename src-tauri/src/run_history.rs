@@ -0,0 +1,84 @@
+// profile 的启动记录：保存在 profile 目录下的 run_history.json，只保留最近若干条
+use crate::{profile_dir_path, write_log};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+const MAX_RECORDS: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct RunRecord {
+    pub started_at: String,
+    pub pid: Option<u32>,
+    pub engine_version: Option<String>,
+    // 代理库中的代理 ID
+    pub proxy_id: Option<String>,
+    // 展开占位符后的代理用户名（不含密码）
+    pub proxy_username: Option<String>,
+    pub exit_ip: Option<String>,
+}
+
+fn history_path(label: &str) -> PathBuf {
+    profile_dir_path(label).join("run_history.json")
+}
+
+pub(crate) fn load(label: &str) -> Vec<RunRecord> {
+    fs::read_to_string(history_path(label))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+// 追加后按上限裁剪旧记录
+fn push_record(records: &mut Vec<RunRecord>, record: RunRecord) {
+    records.push(record);
+    if records.len() > MAX_RECORDS {
+        let excess = records.len() - MAX_RECORDS;
+        records.drain(..excess);
+    }
+}
+
+pub(crate) fn record(label: &str, record: RunRecord) {
+    let mut records = load(label);
+    push_record(&mut records, record);
+    let result = serde_json::to_string_pretty(&records)
+        .map_err(|e| e.to_string())
+        .and_then(|content| fs::write(history_path(label), content).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        write_log(
+            "WARN",
+            &format!("write run history failed label={} err={}", label, e),
+        );
+    }
+}
+
+// 最新的在前
+#[tauri::command]
+pub(crate) fn profile_run_history(label: &str) -> Vec<RunRecord> {
+    let mut records = load(label);
+    records.reverse();
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_is_capped() {
+        let mut records = Vec::new();
+        for i in 0..MAX_RECORDS + 5 {
+            push_record(
+                &mut records,
+                RunRecord {
+                    pid: Some(i as u32),
+                    ..Default::default()
+                },
+            );
+        }
+        assert_eq!(records.len(), MAX_RECORDS);
+        assert_eq!(records[0].pid, Some(5));
+        assert_eq!(records.last().unwrap().pid, Some(MAX_RECORDS as u32 + 4));
+    }
+}
@@ -0,0 +1,431 @@
+// 运行中引擎的内存状态，以及管理器重启后的重新接管
+use crate::{
+    emulation, profile_dir_path, profiles_dir_path, relay, spawn_exit_monitor, supervisor, vault,
+    write_log, LaunchSpec,
+};
+use chrono::Local;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+static SESSIONS: OnceCell<Mutex<HashMap<String, EngineSession>>> = OnceCell::new();
+static LAST_REPORT: OnceCell<Mutex<ReconcileReport>> = OnceCell::new();
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EngineSession {
+    pub label: String,
+    pub pid: u32,
+    pub started_at: String,
+    // 由重新接管恢复，而非本次进程内启动
+    pub reattached: bool,
+    pub remote_debugging: bool,
+    pub devtools_http: Option<String>,
+    pub devtools_ws: Option<String>,
+}
+
+// 随 pid 文件一起落盘，供重新接管时恢复启动参数
+#[derive(Debug, Serialize, Deserialize)]
+struct LaunchRecord {
+    pid: u32,
+    started_at: String,
+    spec: LaunchSpec,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SidecarStatus {
+    pub kind: String,
+    pub ok: bool,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReattachedProfile {
+    pub label: String,
+    pub pid: u32,
+    pub sidecars: Vec<SidecarStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct ReconcileReport {
+    pub attached: Vec<ReattachedProfile>,
+    pub stale: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+fn sessions() -> &'static Mutex<HashMap<String, EngineSession>> {
+    SESSIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn last_report() -> &'static Mutex<ReconcileReport> {
+    LAST_REPORT.get_or_init(|| Mutex::new(ReconcileReport::default()))
+}
+
+fn launch_record_path(label: &str) -> PathBuf {
+    profile_dir_path(label).join("launch.json")
+}
+
+pub(crate) fn devtools_port_file(label: &str) -> PathBuf {
+    vault::user_data_dir(label).join("DevToolsActivePort")
+}
+
+// DevToolsActivePort 第一行为端口，第二行为 browser target 路径
+fn parse_devtools_active_port(content: &str) -> Option<(String, String)> {
+    let mut lines = content.lines();
+    let port: u16 = lines.next()?.trim().parse().ok()?;
+    let path = lines.next().map(|l| l.trim()).unwrap_or("");
+    let http = format!("http://127.0.0.1:{}", port);
+    let ws = format!("ws://127.0.0.1:{}{}", port, path);
+    Some((http, ws))
+}
+
+fn read_devtools_endpoint(label: &str) -> Option<(String, String)> {
+    let content = fs::read_to_string(devtools_port_file(label)).ok()?;
+    parse_devtools_active_port(&content)
+}
+
+fn insert_session(label: &str, pid: u32, started_at: String, spec: &LaunchSpec, reattached: bool) {
+    let endpoint = if spec.remote_debugging {
+        read_devtools_endpoint(label)
+    } else {
+        None
+    };
+    let session = EngineSession {
+        label: label.to_string(),
+        pid,
+        started_at,
+        reattached,
+        remote_debugging: spec.remote_debugging,
+        devtools_http: endpoint.as_ref().map(|e| e.0.clone()),
+        devtools_ws: endpoint.map(|e| e.1),
+    };
+    if let Ok(mut m) = sessions().lock() {
+        m.insert(label.to_string(), session);
+    }
+}
+
+// launch_engine 拿到 pid 后调用：记录内存状态并写入 launch.json
+pub(crate) fn register(label: &str, pid: u32, spec: &LaunchSpec) {
+    let started_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let record = LaunchRecord {
+        pid,
+        started_at: started_at.clone(),
+        spec: spec.clone(),
+    };
+    match serde_json::to_string_pretty(&record) {
+        Ok(content) => {
+            if let Err(e) = fs::write(launch_record_path(label), content) {
+                write_log(
+                    "WARN",
+                    &format!("write launch record failed label={} err={}", label, e),
+                );
+            }
+        }
+        Err(e) => write_log(
+            "WARN",
+            &format!("serialize launch record failed label={} err={}", label, e),
+        ),
+    }
+    insert_session(label, pid, started_at, spec, false);
+}
+
+// pid 为 None 时无条件移除
+pub(crate) fn unregister(label: &str, pid: Option<u32>) {
+    if let Ok(mut m) = sessions().lock() {
+        let matches = m
+            .get(label)
+            .map(|s| pid.is_none_or(|p| p == s.pid))
+            .unwrap_or(false);
+        if matches {
+            m.remove(label);
+        }
+    }
+}
+
+pub(crate) fn running_count() -> usize {
+    sessions().lock().map(|m| m.len()).unwrap_or(0)
+}
+
+pub(crate) fn session(label: &str) -> Option<EngineSession> {
+    let mut m = sessions().lock().ok()?;
+    let s = m.get_mut(label)?;
+    // 端口文件在引擎启动后才写出，首次查询时补齐
+    if s.remote_debugging && s.devtools_ws.is_none() {
+        if let Some((http, ws)) = read_devtools_endpoint(label) {
+            s.devtools_http = Some(http);
+            s.devtools_ws = Some(ws);
+        }
+    }
+    Some(s.clone())
+}
+
+fn read_pid_file(profile_dir: &Path) -> Option<u32> {
+    fs::read_to_string(profile_dir.join("pid"))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn read_launch_record(label: &str) -> Option<LaunchRecord> {
+    let content = fs::read_to_string(launch_record_path(label)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+// 确认 pid 仍是本 profile 的引擎，防止 pid 被其他进程复用后误杀
+// 存活检测与退出监控都依赖 kill/ps，因此重新接管只支持 unix，见 reconcile
+#[allow(unused_variables)]
+fn process_matches_profile(pid: u32, profile_dir: &Path) -> Result<bool, String> {
+    #[cfg(target_family = "unix")]
+    {
+        if !crate::pid_alive_unix(pid) {
+            return Ok(false);
+        }
+        let out = std::process::Command::new("ps")
+            .args(["-p", &pid.to_string(), "-o", "command="])
+            .output()
+            .map_err(|e| format!("ps failed: {e}"))?;
+        let cmdline = String::from_utf8_lossy(&out.stdout);
+        let needle = format!("--user-data-dir={}", profile_dir.to_string_lossy());
+        Ok(cmdline.contains(&needle))
+    }
+    #[cfg(not(target_family = "unix"))]
+    {
+        Err("reattach is only supported on unix".into())
+    }
+}
+
+fn reattach_profile(label: &str, pid: u32, report: &mut ReconcileReport) {
+    let mut sidecars = Vec::new();
+    let record = read_launch_record(label).filter(|r| r.pid == pid);
+    match record {
+        Some(record) => {
+            insert_session(label, pid, record.started_at.clone(), &record.spec, true);
+            // 先登记再监控，进程恰好退出时也能按策略处理
+            supervisor::track(label, pid, record.spec.clone());
+            spawn_exit_monitor(label, pid);
+            sidecars.push(SidecarStatus {
+                kind: "monitor".into(),
+                ok: true,
+                detail: None,
+            });
+            sidecars.push(SidecarStatus {
+                kind: "supervisor".into(),
+                ok: true,
+                detail: Some(format!("{:?}", record.spec.restart.mode)),
+            });
+            // 浏览器仍指向原中转端口，按记录恢复
+            if let Some(result) = relay::resume(label) {
+                sidecars.push(SidecarStatus {
+                    kind: "relay".into(),
+                    ok: result.is_ok(),
+                    detail: Some(match result {
+                        Ok(port) => format!("127.0.0.1:{}", port),
+                        Err(e) => e,
+                    }),
+                });
+            }
+            if record.spec.remote_debugging {
+                let endpoint = read_devtools_endpoint(label);
+                sidecars.push(SidecarStatus {
+                    kind: "devtools".into(),
+                    ok: endpoint.is_some(),
+                    detail: endpoint
+                        .map(|e| e.1)
+                        .or_else(|| Some("DevToolsActivePort not found".into())),
+                });
+                // 时区、地理位置与 CDP 限速随旧连接断开而撤销，需重新下发
+                if let Some(result) = emulation::resume(label) {
+                    sidecars.push(SidecarStatus {
+                        kind: "emulation".into(),
+                        ok: result.is_ok(),
+                        detail: result.err(),
+                    });
+                }
+            }
+        }
+        None => {
+            // 没有启动记录时仍可监控退出，但无法按原参数重启
+            let now = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+            if let Ok(mut m) = sessions().lock() {
+                m.insert(
+                    label.to_string(),
+                    EngineSession {
+                        label: label.to_string(),
+                        pid,
+                        started_at: now,
+                        reattached: true,
+                        remote_debugging: false,
+                        devtools_http: None,
+                        devtools_ws: None,
+                    },
+                );
+            }
+            spawn_exit_monitor(label, pid);
+            sidecars.push(SidecarStatus {
+                kind: "monitor".into(),
+                ok: true,
+                detail: None,
+            });
+            sidecars.push(SidecarStatus {
+                kind: "supervisor".into(),
+                ok: false,
+                detail: Some("launch.json missing or pid mismatch".into()),
+            });
+        }
+    }
+    write_log(
+        "INFO",
+        &format!(
+            "reconcile reattached label={} pid={} sidecars={}",
+            label,
+            pid,
+            sidecars
+                .iter()
+                .map(|s| format!("{}:{}", s.kind, if s.ok { "ok" } else { "failed" }))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+    );
+    report.attached.push(ReattachedProfile {
+        label: label.to_string(),
+        pid,
+        sidecars,
+    });
+}
+
+// 扫描 profiles 目录，校验 pid 文件记录的进程并重建内存状态；已登记的 profile 跳过
+// 仅 unix：其他平台无法校验 pid 也无法监控退出，直接跳过，上次遗留的浏览器不会被接管
+pub(crate) fn reconcile() -> ReconcileReport {
+    let mut report = ReconcileReport::default();
+    let root = profiles_dir_path();
+    let Ok(entries) = fs::read_dir(&root) else {
+        return report;
+    };
+    if cfg!(not(target_family = "unix")) {
+        let leftover: Vec<String> = entries
+            .flatten()
+            .filter(|e| e.path().join("pid").is_file())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        if !leftover.is_empty() {
+            report.warnings.push(format!(
+                "reattach is only supported on unix, not reattached: {}",
+                leftover.join(",")
+            ));
+        }
+        write_log(
+            "INFO",
+            "reconcile skipped: reattach is only supported on unix",
+        );
+        return report;
+    }
+    for entry in entries.flatten() {
+        let profile_dir = entry.path();
+        if !profile_dir.is_dir() {
+            continue;
+        }
+        let Some(label) = profile_dir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(|s| s.to_string())
+        else {
+            continue;
+        };
+        let Some(pid) = read_pid_file(&profile_dir) else {
+            continue;
+        };
+        let known = sessions()
+            .lock()
+            .map(|m| m.contains_key(&label))
+            .unwrap_or(false);
+        if known {
+            continue;
+        }
+        match process_matches_profile(pid, &vault::user_data_dir(&label)) {
+            Ok(true) => reattach_profile(&label, pid, &mut report),
+            Ok(false) => {
+                let _ = fs::remove_file(profile_dir.join("pid"));
+                vault::reseal_stale(&label);
+                write_log(
+                    "INFO",
+                    &format!(
+                        "reconcile removed stale pid file label={} pid={}",
+                        label, pid
+                    ),
+                );
+                report.stale.push(label);
+            }
+            Err(e) => {
+                report.warnings.push(format!(
+                    "cannot validate label={} pid={}: {}",
+                    label, pid, e
+                ));
+            }
+        }
+    }
+    write_log(
+        "INFO",
+        &format!(
+            "reconcile done attached={} stale={} warnings={}",
+            report.attached.len(),
+            report.stale.len(),
+            report.warnings.len()
+        ),
+    );
+    if let Ok(mut last) = last_report().lock() {
+        *last = report.clone();
+    }
+    report
+}
+
+#[tauri::command]
+pub(crate) fn browser_session_info(label: &str) -> Option<EngineSession> {
+    session(label)
+}
+
+#[tauri::command]
+pub(crate) fn browser_sessions() -> Vec<EngineSession> {
+    let labels: Vec<String> = sessions()
+        .lock()
+        .map(|m| m.keys().cloned().collect())
+        .unwrap_or_default();
+    let mut out: Vec<EngineSession> = labels.iter().filter_map(|l| session(l)).collect();
+    out.sort_by(|a, b| a.label.cmp(&b.label));
+    out
+}
+
+#[tauri::command]
+pub(crate) fn browser_reconcile() -> ReconcileReport {
+    reconcile()
+}
+
+#[tauri::command]
+pub(crate) fn browser_reconcile_report() -> ReconcileReport {
+    last_report().lock().map(|r| r.clone()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_devtools_active_port() {
+        let (http, ws) =
+            parse_devtools_active_port("53211\n/devtools/browser/5d1e-4c2a\n").unwrap();
+        assert_eq!(http, "http://127.0.0.1:53211");
+        assert_eq!(ws, "ws://127.0.0.1:53211/devtools/browser/5d1e-4c2a");
+        assert!(parse_devtools_active_port("not-a-port\n").is_none());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn test_process_matches_profile_rejects_foreign_pid() {
+        // 当前测试进程存活，但命令行不含该 profile 的 user-data-dir
+        let me = std::process::id();
+        let res = process_matches_profile(me, Path::new("/nonexistent/libre-profile"));
+        assert_eq!(res, Ok(false));
+    }
+}
@@ -0,0 +1,316 @@
+// profile 快照：压缩保存到 data_local_base()/snapshots/<label>/<id>.zip，元信息写在同名 .json
+use crate::profile_archive::collect_profile_files;
+use crate::profile_store::{self, ProfileConfig};
+use crate::{
+    app_settings, browser_running, data_local_base, profile_dir_path, profiles_dir_path, vault,
+    write_log,
+};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    pub id: String,
+    pub label: String,
+    pub created_at: String,
+    pub note: Option<String>,
+    pub files: usize,
+    // 压缩前总大小
+    pub bytes: u64,
+    // 快照文件大小
+    pub size: u64,
+}
+
+fn snapshots_dir(label: &str) -> PathBuf {
+    data_local_base().join("snapshots").join(label)
+}
+
+// id 只由时间戳生成，校验后才拼接路径
+fn valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_digit() || c == '-')
+}
+
+fn snapshot_paths(label: &str, id: &str) -> Result<(PathBuf, PathBuf), String> {
+    if !valid_id(id) {
+        return Err(format!("invalid snapshot id: {}", id));
+    }
+    let dir = snapshots_dir(label);
+    Ok((
+        dir.join(format!("{}.zip", id)),
+        dir.join(format!("{}.json", id)),
+    ))
+}
+
+// 快照与回滚都要求浏览器已关闭，且没有残留的 vault 解密目录
+fn ensure_closed(label: &str, action: &str) -> Result<(), String> {
+    if browser_running(label).is_some() {
+        return Err(format!(
+            "profile {} is running, close it before {}",
+            label, action
+        ));
+    }
+    if vault::user_data_dir(label) != profile_dir_path(label) {
+        return Err(format!(
+            "profile {} has an open vault mount, unlock the vault to reseal it first",
+            label
+        ));
+    }
+    Ok(())
+}
+
+fn write_snapshot(src: &Path, zip_path: &Path) -> Result<(usize, u64), String> {
+    let files = collect_profile_files(src, true)?;
+    let out = File::create(zip_path).map_err(|e| format!("create snapshot failed: {e}"))?;
+    let mut zip = zip::ZipWriter::new(out);
+    let opts =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    let mut bytes = 0u64;
+    for rel in &files {
+        let data = fs::read(src.join(rel)).map_err(|e| format!("read profile file failed: {e}"))?;
+        let name = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+        zip.start_file(name, opts)
+            .map_err(|e| format!("zip start file failed: {e}"))?;
+        zip.write_all(&data)
+            .map_err(|e| format!("zip write failed: {e}"))?;
+        bytes += data.len() as u64;
+    }
+    zip.finish()
+        .map_err(|e| format!("zip finish failed: {e}"))?;
+    Ok((files.len(), bytes))
+}
+
+fn extract_snapshot(zip_path: &Path, dest: &Path) -> Result<(), String> {
+    let file = File::open(zip_path).map_err(|e| format!("open snapshot failed: {e}"))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("open zip failed: {e}"))?;
+    for i in 0..archive.len() {
+        let mut f = archive
+            .by_index(i)
+            .map_err(|e| format!("snapshot zip idx {i} failed: {e}"))?;
+        let Some(rel) = f.enclosed_name().map(|p| p.to_path_buf()) else {
+            continue;
+        };
+        let out = dest.join(rel);
+        if let Some(parent) = out.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("mkdir failed: {e}"))?;
+        }
+        let mut buf = Vec::new();
+        f.read_to_end(&mut buf)
+            .map_err(|e| format!("read snapshot entry failed: {e}"))?;
+        fs::write(&out, buf).map_err(|e| format!("write snapshot entry failed: {e}"))?;
+    }
+    Ok(())
+}
+
+fn list_infos(label: &str) -> Vec<SnapshotInfo> {
+    let mut out: Vec<SnapshotInfo> = fs::read_dir(snapshots_dir(label))
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().and_then(|x| x.to_str()) == Some("json"))
+                .filter_map(|p| fs::read_to_string(p).ok())
+                .filter_map(|s| serde_json::from_str::<SnapshotInfo>(&s).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    // id 为时间戳，倒序即最新在前
+    out.sort_by(|a, b| b.id.cmp(&a.id));
+    out
+}
+
+// 超出保留数量的旧快照（输入按最新在前排序），keep 为 0 表示不限
+fn ids_to_prune(sorted_newest_first: &[String], keep: usize) -> Vec<String> {
+    if keep == 0 {
+        return Vec::new();
+    }
+    sorted_newest_first.iter().skip(keep).cloned().collect()
+}
+
+fn remove_snapshot(label: &str, id: &str) -> Result<(), String> {
+    let (zip_path, meta_path) = snapshot_paths(label, id)?;
+    let _ = fs::remove_file(&meta_path);
+    match fs::remove_file(&zip_path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("remove snapshot failed: {e}")),
+    }
+}
+
+// 删除 profile 时一并删除其快照
+pub(crate) fn discard(label: &str) {
+    let dir = snapshots_dir(label);
+    if dir.exists() {
+        if let Err(e) = fs::remove_dir_all(&dir) {
+            write_log(
+                "WARN",
+                &format!("remove snapshots failed label={} err={}", label, e),
+            );
+        }
+    }
+}
+
+#[tauri::command]
+pub(crate) fn profile_snapshot_create(
+    label: &str,
+    note: Option<String>,
+) -> Result<SnapshotInfo, String> {
+    let profile_dir = profile_dir_path(label);
+    if !profile_dir.is_dir() {
+        return Err(format!("profile {} not found", label));
+    }
+    ensure_closed(label, "taking a snapshot")?;
+    let dir = snapshots_dir(label);
+    fs::create_dir_all(&dir).map_err(|e| format!("create snapshots dir failed: {e}"))?;
+    let now = Local::now();
+    let mut id = now.format("%Y%m%d-%H%M%S-%3f").to_string();
+    while dir.join(format!("{}.zip", id)).exists() {
+        id.push('-');
+        id.push('1');
+    }
+    let (zip_path, meta_path) = snapshot_paths(label, &id)?;
+    let (files, bytes) = match write_snapshot(&profile_dir, &zip_path) {
+        Ok(v) => v,
+        Err(e) => {
+            let _ = fs::remove_file(&zip_path);
+            return Err(e);
+        }
+    };
+    let info = SnapshotInfo {
+        id: id.clone(),
+        label: label.to_string(),
+        created_at: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+        note: note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()),
+        files,
+        bytes,
+        size: fs::metadata(&zip_path).map(|m| m.len()).unwrap_or(0),
+    };
+    let content = serde_json::to_string_pretty(&info)
+        .map_err(|e| format!("serialize snapshot info failed: {e}"))?;
+    fs::write(&meta_path, content).map_err(|e| format!("write snapshot info failed: {e}"))?;
+
+    let keep = app_settings::current().max_snapshots_per_profile as usize;
+    let ids: Vec<String> = list_infos(label).into_iter().map(|s| s.id).collect();
+    for old in ids_to_prune(&ids, keep) {
+        if let Err(e) = remove_snapshot(label, &old) {
+            write_log(
+                "WARN",
+                &format!("prune snapshot failed label={} id={} err={}", label, old, e),
+            );
+        }
+    }
+    write_log(
+        "INFO",
+        &format!(
+            "profile_snapshot_create label={} id={} files={} bytes={} size={}",
+            label, id, info.files, info.bytes, info.size
+        ),
+    );
+    Ok(info)
+}
+
+#[tauri::command]
+pub(crate) fn profile_snapshot_list(label: &str) -> Vec<SnapshotInfo> {
+    list_infos(label)
+}
+
+// 回滚后返回快照中的 profile 配置，供前端刷新列表
+#[tauri::command]
+pub(crate) fn profile_snapshot_restore(
+    label: &str,
+    id: &str,
+) -> Result<Option<ProfileConfig>, String> {
+    ensure_closed(label, "restoring a snapshot")?;
+    let (zip_path, _) = snapshot_paths(label, id)?;
+    if !zip_path.is_file() {
+        return Err(format!("snapshot {} of {} not found", id, label));
+    }
+    let root = profiles_dir_path();
+    fs::create_dir_all(&root).map_err(|e| format!("create profiles dir failed: {e}"))?;
+    let stamp = Local::now().format("%Y%m%d%H%M%S%3f").to_string();
+    // 先解压到临时目录，成功后再替换当前 profile 目录
+    let staging = root.join(format!(".snapshot-{}", stamp));
+    if let Err(e) = extract_snapshot(&zip_path, &staging) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e);
+    }
+    let profile_dir = profile_dir_path(label);
+    let previous = root.join(format!(".snapshot-old-{}", stamp));
+    if profile_dir.exists() {
+        fs::rename(&profile_dir, &previous).map_err(|e| {
+            let _ = fs::remove_dir_all(&staging);
+            format!("move current profile aside failed: {e}")
+        })?;
+    }
+    if let Err(e) = fs::rename(&staging, &profile_dir) {
+        let _ = fs::rename(&previous, &profile_dir);
+        let _ = fs::remove_dir_all(&staging);
+        return Err(format!("move restored profile failed: {e}"));
+    }
+    let _ = fs::remove_dir_all(&previous);
+    write_log(
+        "INFO",
+        &format!("profile_snapshot_restore label={} id={}", label, id),
+    );
+    Ok(profile_store::load(label))
+}
+
+#[tauri::command]
+pub(crate) fn profile_snapshot_delete(label: &str, id: &str) -> Result<(), String> {
+    remove_snapshot(label, id)?;
+    write_log(
+        "INFO",
+        &format!("profile_snapshot_delete label={} id={}", label, id),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_id_and_retention() {
+        assert!(valid_id("20240101-120000-123"));
+        assert!(!valid_id("../CHE-1"));
+        assert!(!valid_id(""));
+        let ids: Vec<String> = ["3", "2", "1"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(ids_to_prune(&ids, 2), vec!["1".to_string()]);
+        assert!(ids_to_prune(&ids, 0).is_empty());
+        assert!(ids_to_prune(&ids, 5).is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_round_trip_skips_runtime_and_cache() {
+        let base = std::env::temp_dir().join(format!(
+            "libre-snapshot-test-{}",
+            Local::now().format("%Y%m%d%H%M%S%f")
+        ));
+        let src = base.join("src");
+        fs::create_dir_all(src.join("Default/Cache")).unwrap();
+        fs::write(src.join("Default/Bookmarks"), "{}").unwrap();
+        fs::write(src.join("Default/Cache/data_0"), "cache").unwrap();
+        fs::write(src.join("SingletonLock"), "lock").unwrap();
+        fs::write(src.join("profile.json"), "{\"id\":\"CHE-1\"}").unwrap();
+        let zip_path = base.join("snap.zip");
+        let (files, bytes) = write_snapshot(&src, &zip_path).unwrap();
+        assert_eq!(files, 2);
+        assert_eq!(bytes, 2 + 14);
+        let dest = base.join("dest");
+        extract_snapshot(&zip_path, &dest).unwrap();
+        assert_eq!(
+            fs::read_to_string(dest.join("Default/Bookmarks")).unwrap(),
+            "{}"
+        );
+        assert!(dest.join("profile.json").exists());
+        assert!(!dest.join("SingletonLock").exists());
+        assert!(!dest.join("Default/Cache").exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+}
@@ -0,0 +1,384 @@
+// 运行中浏览器的资源占用：后台线程定期采样整个引擎进程树
+use crate::{browser_running, proc_map, profiles_dir_path, sessions, write_log};
+use chrono::{Local, NaiveDateTime, TimeZone};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+
+static SAMPLES: OnceCell<Mutex<HashMap<String, Sample>>> = OnceCell::new();
+static COLLECTOR_STARTED: OnceCell<()> = OnceCell::new();
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BrowserStats {
+    pub label: String,
+    pub pid: u32,
+    // 相对单核的百分比，多核满载时可超过 100
+    pub cpu_percent: f64,
+    pub rss_bytes: u64,
+    pub threads: Option<u64>,
+    pub processes: usize,
+    pub uptime_secs: Option<u64>,
+    // 仅在开启远程调试时可用
+    pub tabs: Option<usize>,
+    pub sampled_at: String,
+}
+
+struct Sample {
+    stats: BrowserStats,
+    cpu_ms: u64,
+    at: Instant,
+}
+
+// 单个进程的一次快照
+#[derive(Debug, Clone, PartialEq)]
+struct ProcInfo {
+    pid: u32,
+    ppid: u32,
+    rss_bytes: u64,
+    cpu_ms: u64,
+    threads: Option<u64>,
+}
+
+fn samples() -> &'static Mutex<HashMap<String, Sample>> {
+    SAMPLES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// /proc/<pid>/stat：进程名可能含空格和括号，从最后一个 ')' 之后按字段解析
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_proc_stat(content: &str, ticks_per_sec: u64, page_size: u64) -> Option<ProcInfo> {
+    let pid: u32 = content.split_whitespace().next()?.parse().ok()?;
+    let rest = &content[content.rfind(')')? + 1..];
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let ppid: u32 = fields.get(1)?.parse().ok()?;
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    let threads: u64 = fields.get(17)?.parse().ok()?;
+    let rss_pages: u64 = fields.get(21)?.parse().ok()?;
+    Some(ProcInfo {
+        pid,
+        ppid,
+        rss_bytes: rss_pages * page_size,
+        cpu_ms: (utime + stime) * 1000 / ticks_per_sec.max(1),
+        threads: Some(threads),
+    })
+}
+
+// ps 的 time 列：Linux 为 [dd-]hh:mm:ss，macOS 为 m:ss.ss
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+fn parse_cputime(s: &str) -> Option<u64> {
+    let (days, clock) = match s.split_once('-') {
+        Some((d, c)) => (d.parse::<u64>().ok()?, c),
+        None => (0, s),
+    };
+    let mut secs = 0f64;
+    for part in clock.split(':') {
+        secs = secs * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Some(days * 86_400_000 + (secs * 1000.0).round() as u64)
+}
+
+#[cfg_attr(target_os = "linux", allow(dead_code))]
+fn parse_ps_line(line: &str) -> Option<ProcInfo> {
+    let mut it = line.split_whitespace();
+    let pid = it.next()?.parse().ok()?;
+    let ppid = it.next()?.parse().ok()?;
+    let rss_kb: u64 = it.next()?.parse().ok()?;
+    let cpu_ms = parse_cputime(it.next()?)?;
+    Some(ProcInfo {
+        pid,
+        ppid,
+        rss_bytes: rss_kb * 1024,
+        cpu_ms,
+        threads: None,
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn list_processes() -> Result<Vec<ProcInfo>, String> {
+    // USER_HZ 在 Linux 上固定为 100，页大小通过 getconf 获取
+    static PAGE_SIZE: OnceCell<u64> = OnceCell::new();
+    let page_size = *PAGE_SIZE.get_or_init(|| {
+        std::process::Command::new("getconf")
+            .arg("PAGESIZE")
+            .output()
+            .ok()
+            .and_then(|o| String::from_utf8_lossy(&o.stdout).trim().parse().ok())
+            .unwrap_or(4096)
+    });
+    let entries = fs::read_dir("/proc").map_err(|e| format!("read /proc failed: {e}"))?;
+    let mut out = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        if !name.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        // 进程可能在遍历期间退出，读取失败直接跳过
+        if let Ok(content) = fs::read_to_string(entry.path().join("stat")) {
+            if let Some(info) = parse_proc_stat(&content, 100, page_size) {
+                out.push(info);
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(all(target_family = "unix", not(target_os = "linux")))]
+fn list_processes() -> Result<Vec<ProcInfo>, String> {
+    let out = std::process::Command::new("ps")
+        .args(["-A", "-o", "pid=,ppid=,rss=,time="])
+        .output()
+        .map_err(|e| format!("ps failed: {e}"))?;
+    Ok(String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter_map(parse_ps_line)
+        .collect())
+}
+
+#[cfg(not(target_family = "unix"))]
+fn list_processes() -> Result<Vec<ProcInfo>, String> {
+    Err("process stats are not supported on this platform".into())
+}
+
+// 以 root 为起点收集所有子孙进程（含 root 本身）
+fn collect_tree(root: u32, procs: &[ProcInfo]) -> Vec<&ProcInfo> {
+    let mut children: HashMap<u32, Vec<&ProcInfo>> = HashMap::new();
+    for p in procs {
+        children.entry(p.ppid).or_default().push(p);
+    }
+    let mut out = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack: Vec<&ProcInfo> = procs.iter().filter(|p| p.pid == root).collect();
+    while let Some(p) = stack.pop() {
+        if !seen.insert(p.pid) {
+            continue;
+        }
+        out.push(p);
+        if let Some(c) = children.get(&p.pid) {
+            stack.extend(c.iter().copied());
+        }
+    }
+    out
+}
+
+fn cpu_percent(prev_ms: u64, cur_ms: u64, elapsed: Duration) -> f64 {
+    let wall = elapsed.as_millis() as f64;
+    if wall <= 0.0 || cur_ms < prev_ms {
+        return 0.0;
+    }
+    let pct = (cur_ms - prev_ms) as f64 / wall * 100.0;
+    (pct * 10.0).round() / 10.0
+}
+
+// 通过 DevTools HTTP 接口统计 page 类型的 target
+fn count_tabs(devtools_http: &str) -> Option<usize> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_millis(800))
+        .build()
+        .ok()?;
+    let body = client
+        .get(format!("{}/json/list", devtools_http))
+        .send()
+        .ok()?
+        .text()
+        .ok()?;
+    let targets: Vec<serde_json::Value> = serde_json::from_str(&body).ok()?;
+    Some(
+        targets
+            .iter()
+            .filter(|t| t.get("type").and_then(|v| v.as_str()) == Some("page"))
+            .count(),
+    )
+}
+
+// 自行启动的子进程 + pid 文件记录的存活进程
+fn engine_pids() -> HashMap<String, u32> {
+    let mut out = HashMap::new();
+    if let Ok(mut m) = proc_map().lock() {
+        for (label, child) in m.iter_mut() {
+            if matches!(child.try_wait(), Ok(None)) {
+                out.insert(label.clone(), child.id());
+            }
+        }
+    }
+    if let Ok(entries) = fs::read_dir(profiles_dir_path()) {
+        for entry in entries.flatten() {
+            let Some(label) = entry.file_name().to_str().map(|s| s.to_string()) else {
+                continue;
+            };
+            if out.contains_key(&label) {
+                continue;
+            }
+            if let Some(pid) = browser_running(&label) {
+                out.insert(label, pid);
+            }
+        }
+    }
+    out
+}
+
+fn uptime_secs(started_at: &str) -> Option<u64> {
+    let naive = NaiveDateTime::parse_from_str(started_at, "%Y-%m-%d %H:%M:%S").ok()?;
+    let started = Local.from_local_datetime(&naive).single()?;
+    (Local::now() - started).num_seconds().try_into().ok()
+}
+
+fn collect_once() -> Result<(), String> {
+    let pids = engine_pids();
+    if pids.is_empty() {
+        if let Ok(mut s) = samples().lock() {
+            s.clear();
+        }
+        return Ok(());
+    }
+    let procs = list_processes()?;
+    let now = Instant::now();
+    let sampled_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut next = HashMap::new();
+    for (label, pid) in pids {
+        let tree = collect_tree(pid, &procs);
+        if tree.is_empty() {
+            continue;
+        }
+        let cpu_ms: u64 = tree.iter().map(|p| p.cpu_ms).sum();
+        let rss_bytes: u64 = tree.iter().map(|p| p.rss_bytes).sum();
+        let threads = tree.iter().map(|p| p.threads).sum::<Option<u64>>();
+        let session = sessions::session(&label);
+        let uptime = session
+            .as_ref()
+            .filter(|s| s.pid == pid)
+            .and_then(|s| uptime_secs(&s.started_at));
+        let tabs = session
+            .as_ref()
+            .and_then(|s| s.devtools_http.as_deref())
+            .and_then(count_tabs);
+        let prev = samples().lock().ok().and_then(|s| {
+            s.get(&label)
+                .filter(|p| p.stats.pid == pid)
+                .map(|p| (p.cpu_ms, p.at))
+        });
+        let cpu = prev
+            .map(|(prev_ms, at)| cpu_percent(prev_ms, cpu_ms, now.duration_since(at)))
+            .unwrap_or(0.0);
+        let stats = BrowserStats {
+            label: label.clone(),
+            pid,
+            cpu_percent: cpu,
+            rss_bytes,
+            threads,
+            processes: tree.len(),
+            uptime_secs: uptime,
+            tabs,
+            sampled_at: sampled_at.clone(),
+        };
+        next.insert(
+            label,
+            Sample {
+                stats,
+                cpu_ms,
+                at: now,
+            },
+        );
+    }
+    if let Ok(mut s) = samples().lock() {
+        *s = next;
+    }
+    Ok(())
+}
+
+// 应用启动时调用一次，重复调用无效
+pub(crate) fn start_collector() {
+    if COLLECTOR_STARTED.set(()).is_err() {
+        return;
+    }
+    thread::spawn(|| {
+        let mut last_err: Option<String> = None;
+        loop {
+            match collect_once() {
+                Ok(()) => last_err = None,
+                Err(e) => {
+                    // 同样的错误只记录一次
+                    if last_err.as_deref() != Some(e.as_str()) {
+                        write_log("WARN", &format!("stats collector failed: {}", e));
+                    }
+                    last_err = Some(e);
+                }
+            }
+            thread::sleep(SAMPLE_INTERVAL);
+        }
+    });
+}
+
+#[tauri::command]
+pub(crate) fn browser_stats(label: &str) -> Option<BrowserStats> {
+    samples().lock().ok()?.get(label).map(|s| s.stats.clone())
+}
+
+#[tauri::command]
+pub(crate) fn browser_stats_all() -> Vec<BrowserStats> {
+    let mut out: Vec<BrowserStats> = samples()
+        .lock()
+        .map(|s| s.values().map(|v| v.stats.clone()).collect())
+        .unwrap_or_default();
+    out.sort_by(|a, b| a.label.cmp(&b.label));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proc(pid: u32, ppid: u32, rss: u64) -> ProcInfo {
+        ProcInfo {
+            pid,
+            ppid,
+            rss_bytes: rss,
+            cpu_ms: 0,
+            threads: Some(1),
+        }
+    }
+
+    #[test]
+    fn test_parse_proc_stat_handles_spaces_in_name() {
+        let line = "4242 (Chromium Helper (GPU)) S 4200 4200 4200 0 -1 4194560 100 0 0 0 150 50 0 0 20 0 17 0 123456 1000000 2048 18446744073709551615";
+        let p = parse_proc_stat(line, 100, 4096).unwrap();
+        assert_eq!(p.pid, 4242);
+        assert_eq!(p.ppid, 4200);
+        assert_eq!(p.cpu_ms, 2000);
+        assert_eq!(p.threads, Some(17));
+        assert_eq!(p.rss_bytes, 2048 * 4096);
+    }
+
+    #[test]
+    fn test_parse_ps_line_linux_and_macos_time() {
+        let p = parse_ps_line("  501   1 20480 01:02:03").unwrap();
+        assert_eq!((p.pid, p.ppid, p.rss_bytes), (501, 1, 20480 * 1024));
+        assert_eq!(p.cpu_ms, 3_723_000);
+        assert_eq!(parse_cputime("1-00:00:01"), Some(86_401_000));
+        assert_eq!(parse_cputime("0:01.50"), Some(1_500));
+    }
+
+    #[test]
+    fn test_collect_tree_sums_descendants_only() {
+        let procs = vec![
+            proc(10, 1, 100),
+            proc(11, 10, 20),
+            proc(12, 11, 3),
+            proc(20, 1, 1000),
+        ];
+        let tree = collect_tree(10, &procs);
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.iter().map(|p| p.rss_bytes).sum::<u64>(), 123);
+        assert!(collect_tree(99, &procs).is_empty());
+        assert_eq!(cpu_percent(1000, 1500, Duration::from_secs(1)), 50.0);
+        assert_eq!(cpu_percent(1500, 1000, Duration::from_secs(1)), 0.0);
+    }
+}
@@ -0,0 +1,405 @@
+// 崩溃自动重启：引擎退出后按 profile 的重启策略退避重启
+use crate::{launch_engine, launch_queue, vault, write_log, LaunchSpec, ENGINE_QUICK_EXIT_MS};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::process::ExitStatus;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+static SUPERVISED: OnceCell<Mutex<HashMap<String, Supervised>>> = OnceCell::new();
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartMode {
+    #[default]
+    Never,
+    OnCrash,
+    Always,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RestartPolicy {
+    pub mode: RestartMode,
+    // window_secs 时间窗内最多重启次数，超过后放弃
+    pub max_retries: u32,
+    pub window_secs: u64,
+    pub backoff_initial_ms: u64,
+    pub backoff_max_ms: u64,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            mode: RestartMode::Never,
+            max_retries: 5,
+            window_secs: 600,
+            backoff_initial_ms: 1000,
+            backoff_max_ms: 60_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum EngineExit {
+    Clean,
+    Crashed(String),
+}
+
+struct Supervised {
+    pid: u32,
+    spec: LaunchSpec,
+    history: VecDeque<Instant>,
+    stop_requested: bool,
+}
+
+fn supervised() -> &'static Mutex<HashMap<String, Supervised>> {
+    SUPERVISED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// browser_open 成功启动或重新接管后登记，重新计数
+pub(crate) fn track(label: &str, pid: u32, spec: LaunchSpec) {
+    if let Ok(mut m) = supervised().lock() {
+        m.insert(
+            label.to_string(),
+            Supervised {
+                pid,
+                spec,
+                history: VecDeque::new(),
+                stop_requested: false,
+            },
+        );
+    }
+}
+
+// 启动失败或无法取得 pid 时撤销登记
+pub(crate) fn untrack(label: &str) {
+    if let Ok(mut m) = supervised().lock() {
+        m.remove(label);
+    }
+}
+
+// launch_engine 在挂上退出监控之前调用：重启出的新进程立即退出时，监控也能与记录的 pid 对上
+pub(crate) fn note_pid(label: &str, pid: u32) {
+    if let Ok(mut m) = supervised().lock() {
+        if let Some(s) = m.get_mut(label) {
+            s.pid = pid;
+        }
+    }
+}
+
+// browser_close 调用：之后的退出都视为用户主动关闭
+pub(crate) fn note_close_requested(label: &str) {
+    if let Ok(mut m) = supervised().lock() {
+        if let Some(s) = m.get_mut(label) {
+            s.stop_requested = true;
+        }
+    }
+}
+
+// Chromium 运行期间把 profile.exit_type 写为 Crashed，正常退出时改回 Normal
+fn read_exit_type(profile_dir: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(profile_dir.join("Default").join("Preferences")).ok()?;
+    let v: serde_json::Value = serde_json::from_str(&content).ok()?;
+    v.get("profile")?
+        .get("exit_type")?
+        .as_str()
+        .map(|s| s.to_string())
+}
+
+fn classify_exit(
+    status: Option<ExitStatus>,
+    exit_type: Option<&str>,
+    uptime: Duration,
+) -> EngineExit {
+    if let Some(st) = status {
+        return if st.success() {
+            EngineExit::Clean
+        } else {
+            EngineExit::Crashed(format!("status {}", st))
+        };
+    }
+    match exit_type {
+        Some("Crashed") => EngineExit::Crashed("exit_type=Crashed".into()),
+        Some(_) => EngineExit::Clean,
+        None if uptime.as_millis() < ENGINE_QUICK_EXIT_MS => {
+            EngineExit::Crashed("exited quickly".into())
+        }
+        None => EngineExit::Clean,
+    }
+}
+
+// 返回下一次重启前的等待时间；None 表示不重启
+fn next_restart_delay(
+    policy: &RestartPolicy,
+    exit: &EngineExit,
+    history: &mut VecDeque<Instant>,
+    now: Instant,
+) -> Option<Duration> {
+    match (policy.mode, exit) {
+        (RestartMode::Never, _) => return None,
+        (RestartMode::OnCrash, EngineExit::Clean) => return None,
+        _ => {}
+    }
+    let window = Duration::from_secs(policy.window_secs);
+    while let Some(first) = history.front() {
+        if now.duration_since(*first) > window {
+            history.pop_front();
+        } else {
+            break;
+        }
+    }
+    if history.len() as u32 >= policy.max_retries {
+        return None;
+    }
+    let factor = 1u64 << history.len().min(16);
+    let delay = policy
+        .backoff_initial_ms
+        .saturating_mul(factor)
+        .min(policy.backoff_max_ms);
+    history.push_back(now);
+    Some(Duration::from_millis(delay))
+}
+
+// 去掉首次打开的 URL，改为恢复上次会话
+fn restart_args(args: &[String]) -> Vec<String> {
+    let mut out = Vec::with_capacity(args.len() + 1);
+    let mut skip_next = false;
+    for a in args {
+        if skip_next {
+            skip_next = false;
+            continue;
+        }
+        if a == "--new-window" {
+            skip_next = true;
+            continue;
+        }
+        out.push(a.clone());
+    }
+    if !out.iter().any(|a| a == "--restore-last-session") {
+        out.insert(1.min(out.len()), "--restore-last-session".into());
+    }
+    out
+}
+
+// 由退出监控线程调用，会在当前线程内等待退避时间；返回是否已重新启动
+pub(crate) fn handle_engine_exit(
+    label: &str,
+    pid: u32,
+    status: Option<ExitStatus>,
+    uptime: Duration,
+) -> bool {
+    let (exit, delay, policy) = {
+        let Ok(mut m) = supervised().lock() else {
+            return false;
+        };
+        let Some(s) = m.get_mut(label) else {
+            return false;
+        };
+        if s.pid != pid {
+            return false;
+        }
+        if s.stop_requested {
+            write_log(
+                "INFO",
+                &format!("supervisor label={} pid={} closed by request", label, pid),
+            );
+            m.remove(label);
+            return false;
+        }
+        let exit_type = read_exit_type(&vault::user_data_dir(label));
+        let exit = classify_exit(status, exit_type.as_deref(), uptime);
+        let delay = next_restart_delay(&s.spec.restart, &exit, &mut s.history, Instant::now());
+        (exit, delay, s.spec.restart.clone())
+    };
+    let Some(delay) = delay else {
+        write_log(
+            "INFO",
+            &format!(
+                "supervisor label={} pid={} exit={:?} policy={:?} not restarting",
+                label, pid, exit, policy.mode
+            ),
+        );
+        if let Ok(mut m) = supervised().lock() {
+            m.remove(label);
+        }
+        return false;
+    };
+    write_log(
+        "WARN",
+        &format!(
+            "supervisor label={} pid={} exit={:?} restarting in {}ms",
+            label,
+            pid,
+            exit,
+            delay.as_millis()
+        ),
+    );
+    thread::sleep(delay);
+
+    let (spec, attempt) = {
+        let Ok(m) = supervised().lock() else {
+            return false;
+        };
+        let Some(s) = m.get(label) else {
+            return false;
+        };
+        if s.stop_requested || s.pid != pid {
+            write_log(
+                "INFO",
+                &format!("supervisor label={} restart cancelled", label),
+            );
+            return false;
+        }
+        let mut spec = s.spec.clone();
+        spec.args = restart_args(&spec.args);
+        (spec, s.history.len())
+    };
+    // 重启同样受运行数与并发启动数限制
+    let launched =
+        launch_queue::acquire(None, label).and_then(|_permit| launch_engine(label, &spec));
+    match launched {
+        Ok(Some(new_pid)) => {
+            write_log(
+                "INFO",
+                &format!(
+                    "supervisor label={} restarted attempt={} pid={}",
+                    label, attempt, new_pid
+                ),
+            );
+            true
+        }
+        // 拿不到 pid 就无法继续监控，按重启失败处理，由调用方做退出清理
+        Ok(None) => {
+            write_log(
+                "WARN",
+                &format!(
+                    "supervisor label={} restart attempt={} pid unknown, supervision stopped",
+                    label, attempt
+                ),
+            );
+            untrack(label);
+            false
+        }
+        Err(e) => {
+            write_log(
+                "ERROR",
+                &format!(
+                    "supervisor label={} restart attempt={} failed: {}",
+                    label, attempt, e
+                ),
+            );
+            untrack(label);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(mode: RestartMode) -> RestartPolicy {
+        RestartPolicy {
+            mode,
+            max_retries: 3,
+            window_secs: 60,
+            backoff_initial_ms: 100,
+            backoff_max_ms: 250,
+        }
+    }
+
+    #[test]
+    fn test_never_and_on_crash_clean_do_not_restart() {
+        let mut history = VecDeque::new();
+        let now = Instant::now();
+        let crashed = EngineExit::Crashed("status 1".into());
+        assert_eq!(
+            next_restart_delay(&policy(RestartMode::Never), &crashed, &mut history, now),
+            None
+        );
+        assert_eq!(
+            next_restart_delay(
+                &policy(RestartMode::OnCrash),
+                &EngineExit::Clean,
+                &mut history,
+                now
+            ),
+            None
+        );
+        assert!(next_restart_delay(
+            &policy(RestartMode::Always),
+            &EngineExit::Clean,
+            &mut history,
+            now
+        )
+        .is_some());
+    }
+
+    #[test]
+    fn test_backoff_doubles_caps_and_gives_up_within_window() {
+        let p = policy(RestartMode::OnCrash);
+        let crashed = EngineExit::Crashed("status 1".into());
+        let mut history = VecDeque::new();
+        let now = Instant::now();
+        let delays: Vec<_> = (0..4)
+            .map(|_| next_restart_delay(&p, &crashed, &mut history, now))
+            .collect();
+        assert_eq!(
+            delays,
+            vec![
+                Some(Duration::from_millis(100)),
+                Some(Duration::from_millis(200)),
+                Some(Duration::from_millis(250)),
+                None
+            ]
+        );
+        // 时间窗过去后重新计数
+        let later = now + Duration::from_secs(61);
+        assert_eq!(
+            next_restart_delay(&p, &crashed, &mut history, later),
+            Some(Duration::from_millis(100))
+        );
+    }
+
+    #[test]
+    fn test_classify_exit_without_status_uses_exit_type() {
+        let long = Duration::from_secs(60);
+        assert_eq!(
+            classify_exit(None, Some("Crashed"), long),
+            EngineExit::Crashed("exit_type=Crashed".into())
+        );
+        assert_eq!(classify_exit(None, Some("Normal"), long), EngineExit::Clean);
+        assert_eq!(
+            classify_exit(None, None, Duration::from_millis(100)),
+            EngineExit::Crashed("exited quickly".into())
+        );
+    }
+
+    #[test]
+    fn test_restart_args_replace_url_with_restore() {
+        let args: Vec<String> = [
+            "--user-data-dir=/tmp/p",
+            "--no-first-run",
+            "--new-window",
+            "https://example.com",
+            "--test-type",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let out = restart_args(&args);
+        assert_eq!(
+            out,
+            vec![
+                "--user-data-dir=/tmp/p",
+                "--restore-last-session",
+                "--no-first-run",
+                "--test-type"
+            ]
+        );
+    }
+}
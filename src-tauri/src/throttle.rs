@@ -0,0 +1,348 @@
+// 按 profile 的网络限速与离线模拟：经本地中转时由中转执行，关闭中转时在开启远程调试的情况下通过 CDP 设置
+use crate::emulation::{self, Overrides};
+use crate::fingerprint::Rng;
+use crate::profile_store::{self, ProfileConfig};
+use crate::write_log;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// navigator.connection 的取值上限与精度，与 Chromium 一致
+const MAX_DOWNLINK_KBPS: u64 = 10_000;
+const MAX_RTT_MS: u64 = 3_000;
+const RTT_STEP_MS: u64 = 50;
+const DOWNLINK_STEP_KBPS: u64 = 25;
+// 限速前链路本身的往返时间按此估计，rtt 为其与附加延迟之和
+const BASE_RTT_MS: u64 = 50;
+// 模拟丢包重传的最小等待
+const MIN_RETRANSMIT: Duration = Duration::from_millis(200);
+
+// profile.json 中的 throttle 字段
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ThrottleConfig {
+    pub offline: bool,
+    // 下行/上行带宽（kbit/s）
+    pub download_kbps: Option<u64>,
+    pub upload_kbps: Option<u64>,
+    // 每次请求往返增加的延迟（毫秒）
+    pub latency_ms: Option<u64>,
+    // 丢包率（%）
+    pub packet_loss: Option<f64>,
+}
+
+// 与限速一致的 navigator.connection
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionInfo {
+    pub effective_type: String,
+    // Mbit/s
+    pub downlink: f64,
+    pub rtt: u64,
+}
+
+impl ThrottleConfig {
+    pub(crate) fn is_active(&self) -> bool {
+        self.offline
+            || self.download_kbps.is_some()
+            || self.upload_kbps.is_some()
+            || self.latency_ms.is_some()
+            || self.packet_loss.is_some_and(|p| p > 0.0)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        for (name, v) in [
+            ("download", self.download_kbps),
+            ("upload", self.upload_kbps),
+        ] {
+            if v == Some(0) {
+                return Err(format!("{} bandwidth must be at least 1 kbit/s", name));
+            }
+        }
+        if self.latency_ms.is_some_and(|l| l > 60_000) {
+            return Err("latency must not exceed 60000 ms".into());
+        }
+        if let Some(p) = self.packet_loss {
+            if !(0.0..100.0).contains(&p) {
+                return Err(format!("packet loss must be in [0, 100): {}", p));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn round_to(v: u64, step: u64) -> u64 {
+    (v + step / 2) / step * step
+}
+
+// 按 Network Information API 的分档规则换算；离线或未限速时返回 None
+pub(crate) fn connection_info(config: &ThrottleConfig) -> Option<ConnectionInfo> {
+    if config.offline || !config.is_active() {
+        return None;
+    }
+    let rtt = round_to(BASE_RTT_MS + config.latency_ms.unwrap_or(0), RTT_STEP_MS).min(MAX_RTT_MS);
+    let kbps = round_to(
+        config
+            .download_kbps
+            .unwrap_or(MAX_DOWNLINK_KBPS)
+            .min(MAX_DOWNLINK_KBPS),
+        DOWNLINK_STEP_KBPS,
+    );
+    let effective_type = match (rtt, kbps) {
+        (r, k) if r >= 2000 || k < 50 => "slow-2g",
+        (r, k) if r >= 1400 || k < 70 => "2g",
+        (r, k) if r >= 270 || k < 700 => "3g",
+        _ => "4g",
+    };
+    Some(ConnectionInfo {
+        effective_type: effective_type.into(),
+        downlink: kbps as f64 / 1000.0,
+        rtt,
+    })
+}
+
+// 前端以字符串或数字保存
+fn number(v: &Value) -> Option<f64> {
+    v.as_f64()
+        .or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+fn connection_matches(conn: &Value, expected: &ConnectionInfo) -> bool {
+    conn["effectiveType"].as_str() == Some(expected.effective_type.as_str())
+        && number(&conn["downlink"]).is_some_and(|d| (d - expected.downlink).abs() < 1e-6)
+        && number(&conn["rtt"]) == Some(expected.rtt as f64)
+}
+
+// 按限速改写指纹中的 connection（与前端一样以字符串保存）；已一致或无法解析时返回 None
+pub(crate) fn align_fingerprint(raw: &str, config: &ThrottleConfig) -> Option<String> {
+    let expected = connection_info(config)?;
+    let mut fp: Value = serde_json::from_str(raw).ok().filter(Value::is_object)?;
+    if connection_matches(&fp["connection"], &expected) {
+        return None;
+    }
+    if !fp["connection"].is_object() {
+        fp["connection"] = json!({});
+    }
+    let conn = &mut fp["connection"];
+    conn["effectiveType"] = json!(expected.effective_type);
+    conn["downlink"] = json!(expected.downlink.to_string());
+    conn["rtt"] = json!(expected.rtt.to_string());
+    serde_json::to_string(&fp).ok()
+}
+
+// 按带宽排队：同一方向上所有连接共享
+struct Lane {
+    kbps: Option<u64>,
+    next_free: Mutex<Instant>,
+}
+
+impl Lane {
+    fn new(kbps: Option<u64>) -> Self {
+        Self {
+            kbps,
+            next_free: Mutex::new(Instant::now()),
+        }
+    }
+
+    fn pace(&self, bytes: usize) {
+        let Some(kbps) = self.kbps else {
+            return;
+        };
+        let cost = Duration::from_micros(bytes as u64 * 8 * 1000 / kbps);
+        let done = {
+            let Ok(mut next) = self.next_free.lock() else {
+                return;
+            };
+            let start = (*next).max(Instant::now());
+            *next = start + cost;
+            *next
+        };
+        let now = Instant::now();
+        if done > now {
+            thread::sleep(done - now);
+        }
+    }
+}
+
+// 中转使用的限速器，一个 profile 一个
+pub(crate) struct Shaper {
+    config: ThrottleConfig,
+    down: Lane,
+    up: Lane,
+    rng: Mutex<Rng>,
+}
+
+impl Shaper {
+    pub(crate) fn new(config: ThrottleConfig) -> Self {
+        Self {
+            down: Lane::new(config.download_kbps),
+            up: Lane::new(config.upload_kbps),
+            config,
+            rng: Mutex::new(Rng::from_entropy()),
+        }
+    }
+
+    pub(crate) fn offline(&self) -> bool {
+        self.config.offline
+    }
+
+    // 请求发出后第一段响应到达前等待一个往返的延迟
+    pub(crate) fn turnaround(&self) {
+        if let Some(ms) = self.config.latency_ms {
+            thread::sleep(Duration::from_millis(ms));
+        }
+    }
+
+    // 转发一段数据前调用：按带宽排队，并按丢包率模拟重传等待
+    pub(crate) fn pace(&self, outbound: bool, bytes: usize) {
+        if outbound { &self.up } else { &self.down }.pace(bytes);
+        let Some(loss) = self.config.packet_loss.filter(|p| *p > 0.0) else {
+            return;
+        };
+        let lost = self
+            .rng
+            .lock()
+            .map(|mut r| (r.below(10_000) as f64) < loss * 100.0)
+            .unwrap_or(false);
+        if lost {
+            let rto = Duration::from_millis(self.config.latency_ms.unwrap_or(0) * 2);
+            thread::sleep(rto.max(MIN_RETRANSMIT));
+        }
+    }
+}
+
+// 经中转时返回限速器；未限速时返回 None
+pub(crate) fn shaper_for(label: &str) -> Option<Shaper> {
+    let config = profile_store::load(label)?.throttle;
+    (config.is_active() && config.validate().is_ok()).then(|| Shaper::new(config))
+}
+
+// 启动前调用：校验设置，并把指纹中的网络信息改为与限速一致
+pub(crate) fn check_before_launch(label: &str) -> Result<Option<ThrottleConfig>, String> {
+    let mut config: ProfileConfig = profile_store::load(label).unwrap_or_default();
+    if !config.throttle.is_active() {
+        return Ok(None);
+    }
+    config
+        .throttle
+        .validate()
+        .map_err(|e| format!("profile {} throttling invalid: {}", label, e))?;
+    let aligned = config
+        .fingerprint
+        .as_deref()
+        .and_then(|raw| align_fingerprint(raw, &config.throttle));
+    if let Some(fp) = aligned {
+        config.fingerprint = Some(fp);
+        profile_store::save(label, &config)?;
+        write_log(
+            "INFO",
+            &format!("throttle aligned fingerprint connection label={}", label),
+        );
+    }
+    Ok(Some(config.throttle))
+}
+
+// 网络条件经常驻连接作用于所有页面，包括之后新开的
+fn cdp_overrides(config: &ThrottleConfig) -> Overrides {
+    // CDP 的吞吐量单位为字节/秒，-1 表示不限
+    let throughput = |kbps: Option<u64>| kbps.map_or(-1.0, |k| k as f64 * 1000.0 / 8.0);
+    let conditions = json!({
+        "offline": config.offline,
+        "latency": config.latency_ms.unwrap_or(0),
+        "downloadThroughput": throughput(config.download_kbps),
+        "uploadThroughput": throughput(config.upload_kbps),
+        "packetLoss": config.packet_loss.unwrap_or(0.0),
+    });
+    Overrides {
+        browser: Vec::new(),
+        session: vec![
+            ("Network.enable".into(), json!({})),
+            ("Network.emulateNetworkConditions".into(), conditions),
+        ],
+    }
+}
+
+// 未经中转时在后台通过 CDP 设置，需开启远程调试
+pub(crate) fn apply_after_launch(label: &str, config: ThrottleConfig) {
+    let label = label.to_string();
+    thread::spawn(
+        move || match emulation::add(&label, cdp_overrides(&config)) {
+            Ok(()) => write_log("INFO", &format!("throttle applied via cdp label={}", label)),
+            Err(e) => write_log(
+                "WARN",
+                &format!("throttle via cdp failed label={} err={}", label, e),
+            ),
+        },
+    );
+}
+
+// 前端按限速设置填写指纹中的网络信息
+#[tauri::command]
+pub(crate) fn throttle_connection(
+    config: ThrottleConfig,
+) -> Result<Option<ConnectionInfo>, String> {
+    config.validate()?;
+    Ok(connection_info(&config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_info_and_fingerprint_match() {
+        let slow = ThrottleConfig {
+            download_kbps: Some(400),
+            latency_ms: Some(400),
+            ..Default::default()
+        };
+        let info = connection_info(&slow).unwrap();
+        assert_eq!(info.effective_type, "3g");
+        assert_eq!((info.downlink, info.rtt), (0.4, 450));
+        let fast = ThrottleConfig {
+            latency_ms: Some(20),
+            ..Default::default()
+        };
+        let info = connection_info(&fast).unwrap();
+        assert_eq!(
+            (info.effective_type.as_str(), info.downlink, info.rtt),
+            ("4g", 10.0, 50)
+        );
+        let very_slow = ThrottleConfig {
+            download_kbps: Some(20),
+            ..Default::default()
+        };
+        assert_eq!(
+            connection_info(&very_slow).unwrap().effective_type,
+            "slow-2g"
+        );
+        assert!(connection_info(&ThrottleConfig::default()).is_none());
+
+        let fp = r#"{"connection":{"effectiveType":"3g","downlink":"0.4","rtt":"450"}}"#;
+        assert!(align_fingerprint(fp, &slow).is_none());
+        let default_fp = r#"{"ua":"x","connection":{"effectiveType":"4g","downlink":"10","rtt":"50","saveData":false}}"#;
+        let aligned: Value =
+            serde_json::from_str(&align_fingerprint(default_fp, &slow).unwrap()).unwrap();
+        assert_eq!(
+            aligned,
+            json!({ "ua": "x", "connection": {
+                "effectiveType": "3g", "downlink": "0.4", "rtt": "450", "saveData": false } })
+        );
+        assert!(align_fingerprint(fp, &very_slow).is_some());
+
+        assert!(ThrottleConfig {
+            packet_loss: Some(100.0),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+        assert!(ThrottleConfig {
+            upload_kbps: Some(0),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+}
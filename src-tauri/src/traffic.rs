@@ -0,0 +1,325 @@
+// 经本地中转的流量统计：按 profile 与代理累计，按天保存在 data_local_base()/traffic/<日期>.json
+use crate::{data_local_base, write_log};
+use chrono::{Duration as ChronoDuration, Local, NaiveDate};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+const MAX_RANGE_DAYS: i64 = 366;
+
+static BOOK: OnceCell<Mutex<Book>> = OnceCell::new();
+static FLUSHER_STARTED: OnceCell<()> = OnceCell::new();
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct Counter {
+    // 浏览器发出的字节数
+    pub sent: u64,
+    // 浏览器收到的字节数
+    pub received: u64,
+}
+
+impl Counter {
+    fn total(&self) -> u64 {
+        self.sent + self.received
+    }
+
+    fn add(&mut self, other: &Counter) {
+        self.sent += other.sent;
+        self.received += other.received;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(default)]
+pub struct DayUsage {
+    pub profiles: BTreeMap<String, Counter>,
+    pub proxies: BTreeMap<String, Counter>,
+}
+
+// 当天的统计在内存中累计，定期落盘
+struct Book {
+    day: String,
+    usage: DayUsage,
+    dirty: bool,
+}
+
+// 单个中转连接的计量；超出配额时 add 返回 false
+#[derive(Debug, Clone)]
+pub(crate) struct Meter {
+    pub label: String,
+    pub proxy_key: String,
+    // 每日配额（字节），发送与接收合计
+    pub profile_quota: Option<u64>,
+    pub proxy_quota: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+pub struct UsageRange {
+    // %Y-%m-%d，缺省为当天
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageRow {
+    pub key: String,
+    pub sent: u64,
+    pub received: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DayTotal {
+    pub date: String,
+    pub sent: u64,
+    pub received: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    pub from: String,
+    pub to: String,
+    pub days: Vec<DayTotal>,
+    pub profiles: Vec<UsageRow>,
+    pub proxies: Vec<UsageRow>,
+}
+
+fn today() -> String {
+    Local::now().format("%Y-%m-%d").to_string()
+}
+
+fn day_path(day: &str) -> PathBuf {
+    data_local_base()
+        .join("traffic")
+        .join(format!("{}.json", day))
+}
+
+fn load_day(day: &str) -> DayUsage {
+    fs::read_to_string(day_path(day))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_day(day: &str, usage: &DayUsage) {
+    let path = day_path(day);
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .map_err(|e| e.to_string())
+        .and_then(|_| serde_json::to_string_pretty(usage).map_err(|e| e.to_string()))
+        .and_then(|content| fs::write(&path, content).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        write_log(
+            "WARN",
+            &format!("save traffic usage failed day={} err={}", day, e),
+        );
+    }
+}
+
+fn book() -> &'static Mutex<Book> {
+    BOOK.get_or_init(|| {
+        let day = today();
+        Mutex::new(Book {
+            usage: load_day(&day),
+            day,
+            dirty: false,
+        })
+    })
+}
+
+// 跨天时先保存前一天，再载入新的一天
+fn roll_over(b: &mut Book) {
+    let now = today();
+    if b.day == now {
+        return;
+    }
+    if b.dirty {
+        save_day(&b.day, &b.usage);
+    }
+    b.usage = load_day(&now);
+    b.day = now;
+    b.dirty = false;
+}
+
+fn within(quota: Option<u64>, counter: Option<&Counter>) -> bool {
+    quota.is_none_or(|q| counter.map(|c| c.total()).unwrap_or(0) < q)
+}
+
+impl Meter {
+    // 新连接前检查当日用量是否已达配额
+    pub(crate) fn allowed(&self) -> bool {
+        let Ok(mut b) = book().lock() else {
+            return true;
+        };
+        roll_over(&mut b);
+        within(self.profile_quota, b.usage.profiles.get(&self.label))
+            && within(self.proxy_quota, b.usage.proxies.get(&self.proxy_key))
+    }
+
+    pub(crate) fn add(&self, sent: u64, received: u64) -> bool {
+        let Ok(mut b) = book().lock() else {
+            return true;
+        };
+        roll_over(&mut b);
+        let delta = Counter { sent, received };
+        let profile = b.usage.profiles.entry(self.label.clone()).or_default();
+        profile.add(&delta);
+        let profile_ok = self.profile_quota.is_none_or(|q| profile.total() <= q);
+        let proxy = b.usage.proxies.entry(self.proxy_key.clone()).or_default();
+        proxy.add(&delta);
+        let proxy_ok = self.proxy_quota.is_none_or(|q| proxy.total() <= q);
+        b.dirty = true;
+        profile_ok && proxy_ok
+    }
+}
+
+pub(crate) fn flush() {
+    if let Ok(mut b) = book().lock() {
+        roll_over(&mut b);
+        if b.dirty {
+            save_day(&b.day, &b.usage);
+            b.dirty = false;
+        }
+    }
+}
+
+// 应用启动时调用一次，重复调用无效
+pub(crate) fn start_flusher() {
+    if FLUSHER_STARTED.set(()).is_err() {
+        return;
+    }
+    thread::spawn(|| loop {
+        thread::sleep(FLUSH_INTERVAL);
+        flush();
+    });
+}
+
+fn parse_day(s: Option<&str>, fallback: NaiveDate) -> Result<NaiveDate, String> {
+    match s.map(str::trim).filter(|s| !s.is_empty()) {
+        Some(s) => {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|e| format!("invalid date {}: {e}", s))
+        }
+        None => Ok(fallback),
+    }
+}
+
+fn rows(map: BTreeMap<String, Counter>) -> Vec<UsageRow> {
+    let mut out: Vec<UsageRow> = map
+        .into_iter()
+        .map(|(key, c)| UsageRow {
+            key,
+            sent: c.sent,
+            received: c.received,
+        })
+        .collect();
+    out.sort_by_key(|r| std::cmp::Reverse(r.sent + r.received));
+    out
+}
+
+// 按天汇总为区间报表，用量大的在前
+pub(crate) fn summarize(from: &str, to: &str, days: Vec<(String, DayUsage)>) -> UsageReport {
+    let mut profiles: BTreeMap<String, Counter> = BTreeMap::new();
+    let mut proxies: BTreeMap<String, Counter> = BTreeMap::new();
+    let mut totals = Vec::with_capacity(days.len());
+    for (date, usage) in days {
+        let mut day_total = Counter::default();
+        for (k, c) in usage.profiles {
+            day_total.add(&c);
+            profiles.entry(k).or_default().add(&c);
+        }
+        for (k, c) in usage.proxies {
+            proxies.entry(k).or_default().add(&c);
+        }
+        totals.push(DayTotal {
+            date,
+            sent: day_total.sent,
+            received: day_total.received,
+        });
+    }
+    UsageReport {
+        from: from.to_string(),
+        to: to.to_string(),
+        days: totals,
+        profiles: rows(profiles),
+        proxies: rows(proxies),
+    }
+}
+
+#[tauri::command]
+pub(crate) fn proxy_usage(range: Option<UsageRange>) -> Result<UsageReport, String> {
+    let range = range.unwrap_or_default();
+    let now = Local::now().date_naive();
+    let to = parse_day(range.to.as_deref(), now)?;
+    let from = parse_day(range.from.as_deref(), to)?;
+    if from > to {
+        return Err("usage range start is after its end".into());
+    }
+    if (to - from).num_days() >= MAX_RANGE_DAYS {
+        return Err(format!("usage range is limited to {} days", MAX_RANGE_DAYS));
+    }
+    flush();
+    let mut days = Vec::new();
+    let mut d = from;
+    while d <= to {
+        let date = d.format("%Y-%m-%d").to_string();
+        let usage = load_day(&date);
+        days.push((date, usage));
+        d += ChronoDuration::days(1);
+    }
+    Ok(summarize(
+        &from.format("%Y-%m-%d").to_string(),
+        &to.format("%Y-%m-%d").to_string(),
+        days,
+    ))
+}
+
+// 测试中读取当天某 profile 的 (发送, 接收)
+#[cfg(test)]
+pub(crate) fn tests_snapshot(label: &str) -> (u64, u64) {
+    book()
+        .lock()
+        .ok()
+        .and_then(|b| b.usage.profiles.get(label).map(|c| (c.sent, c.received)))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(profile: &str, proxy: &str, sent: u64, received: u64) -> DayUsage {
+        let c = Counter { sent, received };
+        DayUsage {
+            profiles: BTreeMap::from([(profile.to_string(), c)]),
+            proxies: BTreeMap::from([(proxy.to_string(), c)]),
+        }
+    }
+
+    #[test]
+    fn test_summarize_range() {
+        let report = summarize(
+            "2026-01-01",
+            "2026-01-02",
+            vec![
+                ("2026-01-01".into(), usage("CHE-1", "PX-a", 10, 100)),
+                ("2026-01-02".into(), usage("CHE-2", "PX-a", 5, 500)),
+            ],
+        );
+        assert_eq!(report.days.len(), 2);
+        assert_eq!(report.days[1].received, 500);
+        assert_eq!(report.profiles[0].key, "CHE-2");
+        assert_eq!(report.proxies.len(), 1);
+        assert_eq!(
+            (report.proxies[0].sent, report.proxies[0].received),
+            (15, 600)
+        );
+    }
+}
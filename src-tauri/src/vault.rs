@@ -0,0 +1,590 @@
+// 加密保存的 profile：关闭时 user-data-dir 打包加密为 vault.bin，启动时解密到临时目录
+use crate::profile_archive::collect_profile_files;
+use crate::{browser_running, data_local_base, profile_dir_path, profiles_dir_path, write_log};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine as _;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const VAULT_FILE: &str = "vault.bin";
+// 记录当前解密目录，管理器重启后仍能找到
+const MOUNT_FILE: &str = "vault.mount";
+const MAGIC: &[u8; 4] = b"LBV1";
+const KDF_ITERATIONS: u32 = 600_000;
+const VERIFIER_PLAINTEXT: &[u8] = b"libre-browser-vault";
+
+// 这些文件由管理器自身使用，始终留在 profile 目录中不加密；
+// 运行期间仍会被改写，打包进 vault 的话解密时会用旧副本覆盖新内容
+const PLAIN_FILES: &[&str] = &[
+    "profile.json",
+    "assets.json",
+    "run_history.json",
+    "relay.json",
+    "launch.json",
+    "emulation.json",
+    "har",
+    VAULT_FILE,
+    MOUNT_FILE,
+    "vault.tmp",
+];
+
+static KEY: OnceCell<Mutex<Option<[u8; 32]>>> = OnceCell::new();
+static MOUNTS: OnceCell<Mutex<HashMap<String, PathBuf>>> = OnceCell::new();
+// 串行化封存/解封，避免退出监控与 browser_close 同时封存
+static SEAL_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultMeta {
+    salt: String,
+    iterations: u32,
+    // 用主密码加密的固定串，用于校验密码
+    verifier: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultStatus {
+    pub initialized: bool,
+    pub unlocked: bool,
+    pub sealed: Vec<String>,
+    pub mounted: Vec<String>,
+}
+
+fn key_slot() -> &'static Mutex<Option<[u8; 32]>> {
+    KEY.get_or_init(|| Mutex::new(None))
+}
+
+fn mounts() -> &'static Mutex<HashMap<String, PathBuf>> {
+    MOUNTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn vault_meta_path() -> PathBuf {
+    data_local_base().join("vault.json")
+}
+
+fn load_meta() -> Option<VaultMeta> {
+    let content = fs::read_to_string(vault_meta_path()).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<sha2::Sha256>(passphrase.as_bytes(), salt, iterations, &mut key);
+    key
+}
+
+// 格式：MAGIC | nonce(12) | AES-256-GCM 密文
+pub(crate) fn encrypt(key: &[u8; 32], plain: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = Aes256Gcm::new(key.into());
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    let ct = cipher
+        .encrypt(Nonce::from_slice(&nonce), plain)
+        .map_err(|_| "vault encrypt failed".to_string())?;
+    let mut out = Vec::with_capacity(MAGIC.len() + nonce.len() + ct.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ct);
+    Ok(out)
+}
+
+pub(crate) fn decrypt(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, String> {
+    let body = data
+        .strip_prefix(MAGIC.as_slice())
+        .ok_or("not a vault file")?;
+    if body.len() < 12 {
+        return Err("vault file truncated".into());
+    }
+    let (nonce, ct) = body.split_at(12);
+    Aes256Gcm::new(key.into())
+        .decrypt(Nonce::from_slice(nonce), ct)
+        .map_err(|_| "vault decrypt failed: wrong passphrase or corrupted data".to_string())
+}
+
+fn current_key() -> Result<[u8; 32], String> {
+    key_slot()
+        .lock()
+        .ok()
+        .and_then(|k| *k)
+        .ok_or_else(|| "vault is locked, unlock it with the master passphrase first".to_string())
+}
+
+pub(crate) fn is_sealed(label: &str) -> bool {
+    profile_dir_path(label).join(VAULT_FILE).exists()
+}
+
+fn mount_dir(label: &str) -> Option<PathBuf> {
+    if let Some(dir) = mounts().lock().ok()?.get(label) {
+        return Some(dir.clone());
+    }
+    let dir = PathBuf::from(
+        fs::read_to_string(profile_dir_path(label).join(MOUNT_FILE))
+            .ok()?
+            .trim(),
+    );
+    if !dir.is_dir() {
+        return None;
+    }
+    if let Ok(mut m) = mounts().lock() {
+        m.insert(label.to_string(), dir.clone());
+    }
+    Some(dir)
+}
+
+// Chromium 的 --user-data-dir：解密中的 vault profile 指向临时目录
+pub(crate) fn user_data_dir(label: &str) -> PathBuf {
+    mount_dir(label).unwrap_or_else(|| profile_dir_path(label))
+}
+
+fn is_plain_file(rel: &Path) -> bool {
+    rel.components()
+        .next()
+        .and_then(|c| c.as_os_str().to_str())
+        .map(|first| PLAIN_FILES.contains(&first))
+        .unwrap_or(false)
+}
+
+fn pack_dir(dir: &Path) -> Result<Vec<u8>, String> {
+    let files = collect_profile_files(dir, true)?;
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let opts =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    for rel in files.iter().filter(|r| !is_plain_file(r)) {
+        let data = fs::read(dir.join(rel)).map_err(|e| format!("read profile file failed: {e}"))?;
+        let name = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+        zip.start_file(name, opts)
+            .map_err(|e| format!("zip start file failed: {e}"))?;
+        zip.write_all(&data)
+            .map_err(|e| format!("zip write failed: {e}"))?;
+    }
+    let cursor = zip
+        .finish()
+        .map_err(|e| format!("zip finish failed: {e}"))?;
+    Ok(cursor.into_inner())
+}
+
+fn unpack_into(data: &[u8], dest: &Path) -> Result<(), String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(data))
+        .map_err(|e| format!("open vault zip failed: {e}"))?;
+    for i in 0..archive.len() {
+        let mut f = archive
+            .by_index(i)
+            .map_err(|e| format!("vault zip idx {i} failed: {e}"))?;
+        let Some(rel) = f.enclosed_name().map(|p| p.to_path_buf()) else {
+            continue;
+        };
+        // 早先封存的 vault 可能带有管理器文件，不能覆盖目录中较新的版本
+        if is_plain_file(&rel) {
+            continue;
+        }
+        let out = dest.join(rel);
+        if let Some(parent) = out.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("mkdir failed: {e}"))?;
+        }
+        let mut buf = Vec::new();
+        f.read_to_end(&mut buf)
+            .map_err(|e| format!("read vault entry failed: {e}"))?;
+        fs::write(&out, buf).map_err(|e| format!("write vault entry failed: {e}"))?;
+    }
+    Ok(())
+}
+
+// 覆写后删除；SSD 与写时复制文件系统上只能尽力而为
+pub(crate) fn secure_remove_file(path: &Path) {
+    if let Ok(meta) = fs::metadata(path) {
+        if let Ok(mut f) = fs::OpenOptions::new().write(true).open(path) {
+            let zeros = vec![0u8; 64 * 1024];
+            let mut left = meta.len();
+            while left > 0 {
+                let n = left.min(zeros.len() as u64) as usize;
+                if f.write_all(&zeros[..n]).is_err() {
+                    break;
+                }
+                left -= n as u64;
+            }
+            let _ = f.sync_all();
+        }
+    }
+    let _ = fs::remove_file(path);
+}
+
+// 逐个覆写文件，运行期文件（日志、崩溃转储等）也不例外；符号链接与 socket 直接删除
+fn wipe(path: &Path) {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => {
+            if let Ok(entries) = fs::read_dir(path) {
+                for entry in entries.flatten() {
+                    wipe(&entry.path());
+                }
+            }
+            let _ = fs::remove_dir_all(path);
+        }
+        Ok(meta) if meta.is_file() => secure_remove_file(path),
+        _ => {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+// keep 按顶层条目判断
+fn secure_remove_tree(dir: &Path, keep: impl Fn(&Path) -> bool) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match path.strip_prefix(dir) {
+            Ok(rel) if keep(rel) => {}
+            _ => wipe(&path),
+        }
+    }
+}
+
+fn write_sealed(label: &str, key: &[u8; 32], plain_zip: &[u8]) -> Result<(), String> {
+    let sealed = encrypt(key, plain_zip)?;
+    let dir = profile_dir_path(label);
+    let tmp = dir.join("vault.tmp");
+    fs::write(&tmp, sealed).map_err(|e| format!("write vault failed: {e}"))?;
+    fs::rename(&tmp, dir.join(VAULT_FILE)).map_err(|e| format!("replace vault failed: {e}"))
+}
+
+// 解密到临时目录（Linux 优先 /dev/shm），目录权限仅限当前用户
+fn new_mount_dir(label: &str) -> Result<PathBuf, String> {
+    let shm = Path::new("/dev/shm");
+    let base = if cfg!(target_os = "linux") && shm.is_dir() {
+        shm.to_path_buf()
+    } else {
+        std::env::temp_dir()
+    };
+    let mut rnd = [0u8; 6];
+    OsRng.fill_bytes(&mut rnd);
+    let suffix: String = rnd.iter().map(|b| format!("{:02x}", b)).collect();
+    let dir = base.join(format!("libre-vault-{}-{}", label, suffix));
+    fs::create_dir_all(&dir).map_err(|e| format!("create vault mount failed: {e}"))?;
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&dir, fs::Permissions::from_mode(0o700));
+    }
+    Ok(dir)
+}
+
+// browser_open 调用：返回本次启动使用的 user-data-dir，必要时先解密
+pub(crate) fn prepare_user_data_dir(label: &str) -> Result<PathBuf, String> {
+    if !is_sealed(label) {
+        return Ok(profile_dir_path(label));
+    }
+    let _guard = SEAL_LOCK
+        .lock()
+        .map_err(|e| format!("vault lock poisoned: {e}"))?;
+    if let Some(dir) = mount_dir(label) {
+        return Ok(dir);
+    }
+    let key = current_key()?;
+    let data = fs::read(profile_dir_path(label).join(VAULT_FILE))
+        .map_err(|e| format!("read vault failed: {e}"))?;
+    let plain = decrypt(&key, &data)?;
+    let dir = new_mount_dir(label)?;
+    if let Err(e) = unpack_into(&plain, &dir) {
+        secure_remove_tree(&dir, |_| false);
+        let _ = fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+    fs::write(
+        profile_dir_path(label).join(MOUNT_FILE),
+        dir.to_string_lossy().as_bytes(),
+    )
+    .map_err(|e| format!("write vault mount record failed: {e}"))?;
+    if let Ok(mut m) = mounts().lock() {
+        m.insert(label.to_string(), dir.clone());
+    }
+    write_log(
+        "INFO",
+        &format!(
+            "vault mounted label={} dir={}",
+            label,
+            dir.to_string_lossy()
+        ),
+    );
+    Ok(dir)
+}
+
+fn forget_mount(label: &str) {
+    if let Ok(mut m) = mounts().lock() {
+        m.remove(label);
+    }
+    let _ = fs::remove_file(profile_dir_path(label).join(MOUNT_FILE));
+}
+
+// 引擎退出或 browser_close 后调用：重新封存并安全删除明文；未解密时无操作
+pub(crate) fn seal_if_mounted(label: &str) -> Result<(), String> {
+    let Ok(_guard) = SEAL_LOCK.lock() else {
+        return Err("vault lock poisoned".into());
+    };
+    let Some(dir) = mount_dir(label) else {
+        return Ok(());
+    };
+    let key = current_key()?;
+    let plain = pack_dir(&dir)?;
+    write_sealed(label, &key, &plain)?;
+    secure_remove_tree(&dir, |_| false);
+    let _ = fs::remove_dir_all(&dir);
+    forget_mount(label);
+    write_log("INFO", &format!("vault sealed label={}", label));
+    Ok(())
+}
+
+pub(crate) fn seal_after_exit(label: &str) {
+    if let Err(e) = seal_if_mounted(label) {
+        write_log(
+            "ERROR",
+            &format!("vault seal failed label={} err={}", label, e),
+        );
+    }
+}
+
+// 启动时发现的残留解密目录：已解锁时立即封存，否则等解锁后封存
+pub(crate) fn reseal_stale(label: &str) {
+    if mount_dir(label).is_none() {
+        return;
+    }
+    if current_key().is_ok() {
+        seal_after_exit(label);
+    } else {
+        write_log(
+            "WARN",
+            &format!("vault mount of {} left in plaintext until unlock", label),
+        );
+    }
+}
+
+// 删除 profile 时一并清理解密目录
+pub(crate) fn discard(label: &str) {
+    if let Some(dir) = mount_dir(label) {
+        secure_remove_tree(&dir, |_| false);
+        let _ = fs::remove_dir_all(&dir);
+        forget_mount(label);
+    }
+}
+
+fn list_labels(pred: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut out: Vec<String> = fs::read_dir(profiles_dir_path())
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| e.file_name().to_str().map(|s| s.to_string()))
+                .filter(|l| pred(l))
+                .collect()
+        })
+        .unwrap_or_default();
+    out.sort();
+    out
+}
+
+#[tauri::command]
+pub(crate) fn vault_status() -> VaultStatus {
+    VaultStatus {
+        initialized: load_meta().is_some(),
+        unlocked: current_key().is_ok(),
+        sealed: list_labels(is_sealed),
+        mounted: list_labels(|l| mount_dir(l).is_some()),
+    }
+}
+
+fn b64() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::STANDARD
+}
+
+fn install_key(key: [u8; 32]) {
+    if let Ok(mut k) = key_slot().lock() {
+        *k = Some(key);
+    }
+    // 上次异常退出遗留的明文目录，解锁后立即重新封存
+    for label in list_labels(|l| mount_dir(l).is_some() && browser_running(l).is_none()) {
+        seal_after_exit(&label);
+    }
+}
+
+// 首次设置主密码，需输入两次；已设置时报错
+#[tauri::command]
+pub(crate) fn vault_set_passphrase(passphrase: &str, confirm: &str) -> Result<VaultStatus, String> {
+    if load_meta().is_some() {
+        return Err("master passphrase is already set".into());
+    }
+    if passphrase.is_empty() {
+        return Err("passphrase is empty".into());
+    }
+    if passphrase != confirm {
+        return Err("passphrases do not match".into());
+    }
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt, KDF_ITERATIONS);
+    let meta = VaultMeta {
+        salt: b64().encode(salt),
+        iterations: KDF_ITERATIONS,
+        verifier: b64().encode(encrypt(&key, VERIFIER_PLAINTEXT)?),
+    };
+    let content = serde_json::to_string_pretty(&meta)
+        .map_err(|e| format!("serialize vault meta failed: {e}"))?;
+    fs::create_dir_all(data_local_base()).map_err(|e| format!("create data dir failed: {e}"))?;
+    fs::write(vault_meta_path(), content).map_err(|e| format!("write vault meta failed: {e}"))?;
+    install_key(key);
+    write_log("INFO", "vault initialized");
+    Ok(vault_status())
+}
+
+// 校验主密码；尚未设置时报错
+#[tauri::command]
+pub(crate) fn vault_unlock(passphrase: &str) -> Result<VaultStatus, String> {
+    if passphrase.is_empty() {
+        return Err("passphrase is empty".into());
+    }
+    let meta = load_meta().ok_or("master passphrase is not set yet")?;
+    let salt = b64()
+        .decode(&meta.salt)
+        .map_err(|e| format!("invalid vault salt: {e}"))?;
+    let key = derive_key(passphrase, &salt, meta.iterations);
+    let verifier = b64()
+        .decode(&meta.verifier)
+        .map_err(|e| format!("invalid vault verifier: {e}"))?;
+    if decrypt(&key, &verifier).ok().as_deref() != Some(VERIFIER_PLAINTEXT) {
+        write_log("WARN", "vault unlock failed: wrong passphrase");
+        return Err("wrong passphrase".into());
+    }
+    install_key(key);
+    write_log("INFO", "vault unlocked");
+    Ok(vault_status())
+}
+
+// 解密中的 profile 退出时需要密钥重新封存，因此有解密目录时不能锁定
+#[tauri::command]
+pub(crate) fn vault_lock() -> Result<VaultStatus, String> {
+    let mounted = list_labels(|l| mount_dir(l).is_some());
+    if !mounted.is_empty() {
+        return Err(format!(
+            "close encrypted profiles before locking: {}",
+            mounted.join(", ")
+        ));
+    }
+    if let Ok(mut k) = key_slot().lock() {
+        *k = None;
+    }
+    write_log("INFO", "vault locked");
+    Ok(vault_status())
+}
+
+// 把已关闭的 profile 转为加密保存
+#[tauri::command]
+pub(crate) fn vault_enable(label: &str) -> Result<(), String> {
+    if browser_running(label).is_some() {
+        return Err(format!("profile {} is running, close it first", label));
+    }
+    if is_sealed(label) {
+        return Ok(());
+    }
+    let key = current_key()?;
+    let dir = profile_dir_path(label);
+    if !dir.is_dir() {
+        return Err(format!("profile {} not found", label));
+    }
+    let _guard = SEAL_LOCK
+        .lock()
+        .map_err(|e| format!("vault lock poisoned: {e}"))?;
+    let plain = pack_dir(&dir)?;
+    write_sealed(label, &key, &plain)?;
+    // 旧的 chrome_debug.log 与崩溃转储也可能含访问记录，一并安全删除；之后改写到解密目录中
+    secure_remove_tree(&dir, is_plain_file);
+    write_log("INFO", &format!("vault enabled label={}", label));
+    Ok(())
+}
+
+// 解密回 profile 目录并取消加密保存
+#[tauri::command]
+pub(crate) fn vault_disable(label: &str) -> Result<(), String> {
+    if browser_running(label).is_some() {
+        return Err(format!("profile {} is running, close it first", label));
+    }
+    if !is_sealed(label) {
+        return Ok(());
+    }
+    seal_if_mounted(label)?;
+    let key = current_key()?;
+    let _guard = SEAL_LOCK
+        .lock()
+        .map_err(|e| format!("vault lock poisoned: {e}"))?;
+    let dir = profile_dir_path(label);
+    let mut data = Vec::new();
+    File::open(dir.join(VAULT_FILE))
+        .and_then(|mut f| f.read_to_end(&mut data))
+        .map_err(|e| format!("read vault failed: {e}"))?;
+    let plain = decrypt(&key, &data)?;
+    unpack_into(&plain, &dir)?;
+    fs::remove_file(dir.join(VAULT_FILE)).map_err(|e| format!("remove vault failed: {e}"))?;
+    write_log("INFO", &format!("vault disabled label={}", label));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_round_trip_and_wrong_key() {
+        let key = derive_key("correct horse", b"0123456789abcdef", 1000);
+        let sealed = encrypt(&key, b"cookie data").unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert_eq!(decrypt(&key, &sealed).unwrap(), b"cookie data");
+        let wrong = derive_key("battery staple", b"0123456789abcdef", 1000);
+        assert!(decrypt(&wrong, &sealed).is_err());
+        // 同一明文每次使用不同 nonce
+        assert_ne!(encrypt(&key, b"cookie data").unwrap(), sealed);
+    }
+
+    #[test]
+    fn test_pack_unpack_keeps_plain_files_out() {
+        let base = std::env::temp_dir().join(format!("libre-vault-test-{}", std::process::id()));
+        let src = base.join("src");
+        let dst = base.join("dst");
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(src.join("Default")).unwrap();
+        fs::write(src.join("Default").join("Cookies"), b"secret").unwrap();
+        fs::write(src.join("profile.json"), b"{}").unwrap();
+        fs::write(src.join("pid"), b"1").unwrap();
+        fs::create_dir_all(src.join("crashes")).unwrap();
+        fs::write(src.join("crashes").join("dump.dmp"), b"stack").unwrap();
+        fs::write(src.join("chrome_debug.log"), b"visited").unwrap();
+        fs::write(src.join("run_history.json"), b"[]").unwrap();
+        fs::create_dir_all(src.join("har")).unwrap();
+        fs::write(src.join("har").join("a.har"), b"{}").unwrap();
+        let packed = pack_dir(&src).unwrap();
+        unpack_into(&packed, &dst).unwrap();
+        assert!(!dst.join("run_history.json").exists());
+        assert!(!dst.join("har").exists());
+        assert_eq!(
+            fs::read(dst.join("Default").join("Cookies")).unwrap(),
+            b"secret"
+        );
+        assert!(!dst.join("profile.json").exists());
+        assert!(!dst.join("pid").exists());
+        secure_remove_tree(&src, is_plain_file);
+        assert!(src.join("profile.json").exists());
+        assert!(src.join("run_history.json").exists());
+        assert!(src.join("har").join("a.har").exists());
+        assert!(!src.join("Default").exists());
+        // 运行期的日志与崩溃转储同样删除
+        assert!(!src.join("crashes").exists());
+        assert!(!src.join("chrome_debug.log").exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+}
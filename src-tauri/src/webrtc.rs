@@ -0,0 +1,336 @@
+// WebRTC IP 泄漏防护：把指纹里的 webrtcPolicy 转成 Chromium 启动参数与 profile 偏好
+use crate::profile_store::{self, ProfileConfig};
+use crate::{app_settings, geoip, write_log};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 伪装出口 IP 的内置扩展目录（位于 user-data-dir 下，每次启动重新生成）
+const FAKE_IP_EXTENSION_DIR: &str = "LibreExtensions/webrtc";
+// 固定公钥使扩展 ID 与目录无关，策略屏蔽扩展时据此放行
+pub(crate) const FAKE_IP_EXTENSION_KEY: &str = "MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEA7j6orzIHgjqN2kn5e3iLrlXF9lnWKJJtKA7xCfeMeP3/rAYWMccqkqWbxazEy9C231FXrejtxyldtBLZG9KBvPXdHyFRRvuizv4YrRmHswcCy1QD33CGuUGFUMLVyQqigZ0ALYJLes0CvbNHqjrHIzJf74CAzmC1cAtMUhDtm1hfNXbfZTRYQ+xUBe2L/m7EurFopWWcZufrg77ifhfXEXLUVrFnySu87jhKZcidVLMZY2Keg22AEQG7bI0KBoI+tjAhY15qQvHl/3OBjr0DbM31p5wOR4hg+A+leQg+OZUhi3izj3TSVIbb/qJJYWcX17CXcxW839ezB3M9fLUzvQIDAQAB";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebrtcPolicy {
+    #[default]
+    Default,
+    // 只允许经代理的连接，不暴露真实 IP
+    ProxyOnly,
+    // 只暴露默认公网出口，隐藏内网地址
+    DisableLocal,
+    Blocked,
+    // 在 proxy_only 基础上，对页面报告代理出口 IP
+    FakeIp,
+}
+
+impl WebrtcPolicy {
+    pub(crate) fn parse(s: &str) -> Self {
+        match s.trim() {
+            "proxy_only" => Self::ProxyOnly,
+            "disable_local" => Self::DisableLocal,
+            "blocked" => Self::Blocked,
+            "fake_ip" => Self::FakeIp,
+            _ => Self::Default,
+        }
+    }
+
+    // Chromium 的 WebRTC IP handling policy 取值
+    pub(crate) fn ip_handling(self) -> Option<&'static str> {
+        match self {
+            Self::Default => None,
+            Self::DisableLocal => Some("default_public_interface_only"),
+            Self::ProxyOnly | Self::Blocked | Self::FakeIp => Some("disable_non_proxied_udp"),
+        }
+    }
+}
+
+// 读取指纹中的 webrtcPolicy，缺失时为 default
+pub(crate) fn policy_from_config(config: &ProfileConfig) -> WebrtcPolicy {
+    config
+        .fingerprint
+        .as_deref()
+        .and_then(|raw| serde_json::from_str::<Value>(raw).ok())
+        .and_then(|fp| fp.get("webrtcPolicy")?.as_str().map(WebrtcPolicy::parse))
+        .unwrap_or_default()
+}
+
+pub(crate) fn policy_for_label(label: &str) -> WebrtcPolicy {
+    profile_store::load(label)
+        .map(|c| policy_from_config(&c))
+        .unwrap_or_default()
+}
+
+pub(crate) fn launch_args(policy: WebrtcPolicy, fake_ip_extension: Option<&Path>) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(handling) = policy.ip_handling() {
+        args.push(format!("--force-webrtc-ip-handling-policy={}", handling));
+    }
+    if policy == WebrtcPolicy::Blocked {
+        // 未授权媒体权限的页面拿不到任何本地地址
+        args.push("--enforce-webrtc-ip-permission-check".into());
+    }
+    if let Some(dir) = fake_ip_extension {
+        args.push(format!("--load-extension={}", dir.to_string_lossy()));
+    }
+    args
+}
+
+// 同步写入 Default/Preferences 的 webrtc.*，即使不带启动参数打开该目录也保持一致
+pub(crate) fn apply_prefs(user_data_dir: &Path, policy: WebrtcPolicy) -> Result<(), String> {
+    let path = user_data_dir.join("Default").join("Preferences");
+    let mut prefs: Value = fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .filter(|v: &Value| v.is_object())
+        .unwrap_or_else(|| json!({}));
+    let webrtc = match policy.ip_handling() {
+        Some(handling) => json!({
+            "ip_handling_policy": handling,
+            "multiple_routes_enabled": false,
+            "nonproxied_udp_enabled": false,
+        }),
+        None => json!({ "ip_handling_policy": "default" }),
+    };
+    let prev = prefs.get("webrtc").cloned().unwrap_or_else(|| json!({}));
+    let mut merged = prev.as_object().cloned().unwrap_or_default();
+    if policy == WebrtcPolicy::Default {
+        merged.remove("multiple_routes_enabled");
+        merged.remove("nonproxied_udp_enabled");
+    }
+    if let Some(obj) = webrtc.as_object() {
+        merged.extend(obj.clone());
+    }
+    prefs["webrtc"] = Value::Object(merged);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("create profile dir failed: {e}"))?;
+    }
+    let content =
+        serde_json::to_string(&prefs).map_err(|e| format!("serialize preferences failed: {e}"))?;
+    fs::write(&path, content).map_err(|e| format!("write preferences failed: {e}"))
+}
+
+// 页面脚本：把 ICE 候选与 SDP 中的地址改写为出口 IP，收集结束前补一条 srflx 候选
+fn fake_ip_script(ip: &str) -> String {
+    const TEMPLATE: &str = r#"(() => {
+  const IP = __EXIT_IP__;
+  const PC = window.RTCPeerConnection;
+  if (!PC || PC.__libreWrapped) return;
+  const v6 = IP.includes(":");
+  const rewrite = (c) => {
+    const parts = c.split(" ");
+    if (parts.length > 7) parts[4] = IP;
+    return parts.join(" ");
+  };
+  const cand = Object.getOwnPropertyDescriptor(RTCIceCandidate.prototype, "candidate");
+  Object.defineProperty(RTCIceCandidate.prototype, "candidate", {
+    get() { const v = cand.get.call(this); return v ? rewrite(v) : v; },
+    configurable: true,
+  });
+  const addr = Object.getOwnPropertyDescriptor(RTCIceCandidate.prototype, "address");
+  if (addr) Object.defineProperty(RTCIceCandidate.prototype, "address", {
+    get() { return addr.get.call(this) ? IP : null; },
+    configurable: true,
+  });
+  const sdp = Object.getOwnPropertyDescriptor(RTCSessionDescription.prototype, "sdp");
+  Object.defineProperty(RTCSessionDescription.prototype, "sdp", {
+    get() {
+      const v = sdp.get.call(this);
+      return v && v
+        .replace(/^(a=candidate:(?:\S+ ){4})\S+/gm, "$1" + IP)
+        .replace(/^c=IN IP[46] \S+/gm, "c=IN IP" + (v6 ? "6 " : "4 ") + IP);
+    },
+    configurable: true,
+  });
+  class Wrapped extends PC {
+    constructor(...args) {
+      super(...args);
+      let seen = false;
+      super.addEventListener("icecandidate", (e) => {
+        if (e.candidate) { seen = true; return; }
+        if (seen) return;
+        seen = true;
+        const candidate = new RTCIceCandidate({
+          candidate: `candidate:1 1 udp 1686052607 ${IP} 9 typ srflx raddr 0.0.0.0 rport 0 generation 0`,
+          sdpMid: "0",
+          sdpMLineIndex: 0,
+        });
+        this.dispatchEvent(new RTCPeerConnectionIceEvent("icecandidate", { candidate }));
+      });
+    }
+  }
+  Object.defineProperty(Wrapped, "__libreWrapped", { value: true });
+  window.RTCPeerConnection = Wrapped;
+  if (window.webkitRTCPeerConnection) window.webkitRTCPeerConnection = Wrapped;
+})();
+"#;
+    TEMPLATE.replace("__EXIT_IP__", &Value::String(ip.to_string()).to_string())
+}
+
+// 生成以 MAIN world 注入脚本的扩展，返回扩展目录
+pub(crate) fn write_fake_ip_extension(user_data_dir: &Path, ip: &str) -> Result<PathBuf, String> {
+    let dir = user_data_dir.join(FAKE_IP_EXTENSION_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("create webrtc extension dir failed: {e}"))?;
+    let manifest = json!({
+        "manifest_version": 3,
+        "name": "Libre WebRTC",
+        "version": "1.0",
+        "key": FAKE_IP_EXTENSION_KEY,
+        "content_scripts": [{
+            "matches": ["<all_urls>"],
+            "js": ["webrtc.js"],
+            "run_at": "document_start",
+            "all_frames": true,
+            "match_about_blank": true,
+            "world": "MAIN",
+        }],
+    });
+    let content = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("serialize extension manifest failed: {e}"))?;
+    fs::write(dir.join("manifest.json"), content)
+        .map_err(|e| format!("write extension manifest failed: {e}"))?;
+    fs::write(dir.join("webrtc.js"), fake_ip_script(ip))
+        .map_err(|e| format!("write extension script failed: {e}"))?;
+    Ok(dir)
+}
+
+// 伪装模式所需的出口 IP：优先用地理对齐已解析的结果，否则经代理查询
+fn exit_ip_for(proxy: Option<&str>, known_ip: Option<&str>) -> Result<String, String> {
+    if let Some(ip) = known_ip {
+        return Ok(ip.to_string());
+    }
+    let proxy = proxy
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .ok_or_else(|| "no proxy configured".to_string())?;
+    geoip::resolve_exit_ip(proxy, &app_settings::current().ip_echo_url).map(|ip| ip.to_string())
+}
+
+// 启动前调用：写入偏好并返回需追加的启动参数；伪装模式拿不到出口 IP 时退回 proxy_only
+pub(crate) fn prepare_launch(
+    label: &str,
+    user_data_dir: &Path,
+    proxy: Option<&str>,
+    known_ip: Option<&str>,
+) -> Vec<String> {
+    let policy = policy_for_label(label);
+    if let Err(e) = apply_prefs(user_data_dir, policy) {
+        write_log(
+            "WARN",
+            &format!("write webrtc prefs failed label={} err={}", label, e),
+        );
+    }
+    let extension = if policy == WebrtcPolicy::FakeIp {
+        match exit_ip_for(proxy, known_ip)
+            .and_then(|ip| write_fake_ip_extension(user_data_dir, &ip))
+        {
+            Ok(dir) => Some(dir),
+            Err(e) => {
+                write_log(
+                    "WARN",
+                    &format!(
+                        "webrtc fake ip unavailable, fallback to proxy_only label={} err={}",
+                        label, e
+                    ),
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+    if extension.is_none() {
+        let _ = fs::remove_dir_all(user_data_dir.join(FAKE_IP_EXTENSION_DIR));
+    }
+    let args = launch_args(policy, extension.as_deref());
+    if policy != WebrtcPolicy::Default {
+        write_log(
+            "INFO",
+            &format!(
+                "webrtc policy label={} policy={:?} args={:?}",
+                label, policy, args
+            ),
+        );
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "libre-webrtc-{}-{}",
+            name,
+            chrono::Local::now().format("%Y%m%d%H%M%S%f")
+        ))
+    }
+
+    #[test]
+    fn test_launch_args_per_policy() {
+        assert!(launch_args(WebrtcPolicy::parse("default"), None).is_empty());
+        assert!(launch_args(WebrtcPolicy::parse("unknown"), None).is_empty());
+        assert_eq!(
+            launch_args(WebrtcPolicy::parse("proxy_only"), None),
+            vec!["--force-webrtc-ip-handling-policy=disable_non_proxied_udp"]
+        );
+        assert_eq!(
+            launch_args(WebrtcPolicy::parse("disable_local"), None),
+            vec!["--force-webrtc-ip-handling-policy=default_public_interface_only"]
+        );
+        assert_eq!(
+            launch_args(WebrtcPolicy::Blocked, None),
+            vec![
+                "--force-webrtc-ip-handling-policy=disable_non_proxied_udp",
+                "--enforce-webrtc-ip-permission-check"
+            ]
+        );
+        assert_eq!(
+            launch_args(WebrtcPolicy::FakeIp, Some(Path::new("/tmp/ext"))),
+            vec![
+                "--force-webrtc-ip-handling-policy=disable_non_proxied_udp",
+                "--load-extension=/tmp/ext"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prefs_and_fake_ip_extension_files() {
+        let dir = temp_dir("prefs");
+        fs::create_dir_all(dir.join("Default")).unwrap();
+        fs::write(
+            dir.join("Default/Preferences"),
+            r#"{"profile":{"exit_type":"Normal"}}"#,
+        )
+        .unwrap();
+        apply_prefs(&dir, WebrtcPolicy::ProxyOnly).unwrap();
+        let prefs: Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("Default/Preferences")).unwrap())
+                .unwrap();
+        assert_eq!(prefs["profile"]["exit_type"], "Normal");
+        assert_eq!(
+            prefs["webrtc"],
+            json!({
+                "ip_handling_policy": "disable_non_proxied_udp",
+                "multiple_routes_enabled": false,
+                "nonproxied_udp_enabled": false,
+            })
+        );
+        apply_prefs(&dir, WebrtcPolicy::Default).unwrap();
+        let prefs: Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("Default/Preferences")).unwrap())
+                .unwrap();
+        assert_eq!(prefs["webrtc"], json!({ "ip_handling_policy": "default" }));
+
+        let ext = write_fake_ip_extension(&dir, "203.0.113.7").unwrap();
+        let manifest: Value =
+            serde_json::from_str(&fs::read_to_string(ext.join("manifest.json")).unwrap()).unwrap();
+        assert_eq!(manifest["content_scripts"][0]["world"], "MAIN");
+        assert_eq!(manifest["content_scripts"][0]["js"][0], "webrtc.js");
+        let script = fs::read_to_string(ext.join("webrtc.js")).unwrap();
+        assert!(script.contains(r#"const IP = "203.0.113.7";"#));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -15,12 +15,47 @@ const dict: Record<Language, Record<string, string>> = {
     "settings.quota.label": "数量限制",
     "settings.quota.maxBrowsers": "最大可创建的浏览器数量",
     "settings.quota.placeholder": "不设置则不限数量",
+    "settings.quota.maxRunning": "同时运行的浏览器上限",
+    "settings.quota.maxLaunches": "同时启动的浏览器上限",
+    "settings.quota.runtimeHint": "填 0 表示不限；超出上限的启动请求会排队等待",
+    "settings.quota.maxSnapshots": "每个浏览器保留的快照数量（0 表示不限）",
     "settings.cleanup.label": "缓存清理",
     "settings.cleanup.desc": "清理不在浏览器列表中的历史 profile、图标和自定义 app 残留文件。",
     "settings.cleanup.action": "清理历史缓存",
     "settings.cleanup.running": "清理中...",
     "settings.cleanup.done": "清理完成：profiles {profiles} 个，custom apps {apps} 个，icons {icons} 个，告警 {warnings} 条",
     "settings.cleanup.failed": "清理失败",
+    "settings.tabs.exit": "退出行为",
+    "settings.exit.label": "关闭管理器时",
+    "settings.exit.desc": "选择关闭管理器时如何处理仍在运行的浏览器。保留运行的浏览器会在下次启动时重新接管。",
+    "settings.exit.closeAll": "关闭所有浏览器",
+    "settings.exit.detach": "保留浏览器继续运行",
+    "settings.exit.ask": "每次询问",
+    "exitAsk.title": "退出管理器",
+    "exitAsk.message": "仍有 {count} 个浏览器在运行，如何处理？",
+    "exitAsk.remember": "记住我的选择",
+    "exitAsk.cancel": "取消",
+    "settings.tabs.vault": "加密保存",
+    "settings.vault.label": "加密保存",
+    "settings.vault.desc": "开启加密的浏览器关闭后数据会用主密码加密保存，启动时解密到临时目录。主密码丢失后数据无法恢复。",
+    "settings.vault.uninitialized": "未设置主密码",
+    "settings.vault.locked": "已锁定",
+    "settings.vault.unlocked": "已解锁",
+    "settings.vault.count": "加密 {sealed} 个，运行中 {mounted} 个",
+    "settings.vault.passphrase": "主密码",
+    "settings.vault.confirm": "再次输入主密码",
+    "settings.vault.mismatch": "两次输入的主密码不一致",
+    "settings.vault.init": "设置并解锁",
+    "settings.vault.unlock": "解锁",
+    "settings.vault.lock": "锁定",
+    "settings.vault.failed": "操作失败",
+    "settings.tabs.geo": "地理对齐",
+    "settings.geo.label": "按代理出口对齐",
+    "settings.geo.desc": "启动使用代理的浏览器前，经代理获取出口 IP 并查询离线 GeoIP 库，自动设置时区、语言与地理位置（地理位置需开启远程调试）。同一代理的结果缓存 6 小时。",
+    "settings.geo.enabled": "启用自动对齐",
+    "settings.geo.dbPath": "GeoIP 数据库（mmdb）路径",
+    "settings.geo.dbPathPlaceholder": "留空使用数据目录下 geoip/GeoLite2-City.mmdb",
+    "settings.geo.echoUrl": "出口 IP 查询地址",
   },
   "en-US": {
     "settings.title": "Settings",
@@ -36,12 +71,47 @@ const dict: Record<Language, Record<string, string>> = {
     "settings.quota.label": "Quota Limit",
     "settings.quota.maxBrowsers": "Max browser count",
     "settings.quota.placeholder": "Leave blank for unlimited",
+    "settings.quota.maxRunning": "Max running browsers",
+    "settings.quota.maxLaunches": "Max concurrent launches",
+    "settings.quota.runtimeHint": "0 means unlimited; launches beyond the limit wait in a queue",
+    "settings.quota.maxSnapshots": "Snapshots kept per browser (0 means unlimited)",
     "settings.cleanup.label": "Cache Cleanup",
     "settings.cleanup.desc": "Clean residual profile files, icons and custom app bundles that are no longer in the browser list.",
     "settings.cleanup.action": "Clean stale cache",
     "settings.cleanup.running": "Cleaning...",
     "settings.cleanup.done": "Done: profiles {profiles}, custom apps {apps}, icons {icons}, warnings {warnings}",
     "settings.cleanup.failed": "Cleanup failed",
+    "settings.tabs.exit": "On Exit",
+    "settings.exit.label": "When closing the manager",
+    "settings.exit.desc": "Choose what happens to running browsers when the manager closes. Browsers left running are reattached on next start.",
+    "settings.exit.closeAll": "Close all browsers",
+    "settings.exit.detach": "Leave browsers running",
+    "settings.exit.ask": "Ask every time",
+    "exitAsk.title": "Quit manager",
+    "exitAsk.message": "{count} browsers are still running. What should happen to them?",
+    "exitAsk.remember": "Remember my choice",
+    "exitAsk.cancel": "Cancel",
+    "settings.tabs.vault": "Vault",
+    "settings.vault.label": "Encrypted Profiles",
+    "settings.vault.desc": "Encrypted browsers are sealed with the master passphrase when closed and decrypted to a temporary directory on launch. Data cannot be recovered without the passphrase.",
+    "settings.vault.uninitialized": "No master passphrase set",
+    "settings.vault.locked": "Locked",
+    "settings.vault.unlocked": "Unlocked",
+    "settings.vault.count": "{sealed} encrypted, {mounted} open",
+    "settings.vault.passphrase": "Master passphrase",
+    "settings.vault.confirm": "Confirm passphrase",
+    "settings.vault.mismatch": "Passphrases do not match",
+    "settings.vault.init": "Set and unlock",
+    "settings.vault.unlock": "Unlock",
+    "settings.vault.lock": "Lock",
+    "settings.vault.failed": "Operation failed",
+    "settings.tabs.geo": "Geo Alignment",
+    "settings.geo.label": "Follow proxy exit location",
+    "settings.geo.desc": "Before launching a browser with a proxy, resolve its exit IP through the proxy and look it up in the offline GeoIP database to set timezone, language and geolocation (geolocation requires remote debugging). Results are cached per proxy for 6 hours.",
+    "settings.geo.enabled": "Enable automatic alignment",
+    "settings.geo.dbPath": "GeoIP database (mmdb) path",
+    "settings.geo.dbPathPlaceholder": "Leave blank to use geoip/GeoLite2-City.mmdb in the data directory",
+    "settings.geo.echoUrl": "Exit IP echo URL",
   },
 };
 